@@ -0,0 +1,234 @@
+//! python bindings of the simulation, observations are numpy arrays
+
+use numpy::{IntoPyArray, PyArray1, PyArray2, PyArrayMethods};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use rsnake::game::env::{Encoding, Env as RsEnv, Rewards, Turn};
+use rsnake::game::{Autopilot, Game as RsGame, Grid};
+
+/// observation, reward, done and info of a step
+type Step<'py> = (Bound<'py, PyArray1<f32>>, f64, bool, Bound<'py, PyDict>);
+
+fn encoding(name: &str) -> PyResult<Encoding> {
+    Encoding::from_name(name)
+        .ok_or_else(|| PyValueError::new_err(format!("unknown observation: {}", name)))
+}
+
+fn turn(action: usize) -> PyResult<Turn> {
+    Turn::from_index(action)
+        .ok_or_else(|| PyValueError::new_err(format!("unknown action: {}", action)))
+}
+
+fn autopilot(name: Option<&str>) -> PyResult<Autopilot> {
+    match name {
+        None => Ok(Autopilot::None),
+        Some("stupid") => Ok(Autopilot::Stupid),
+        Some("smart") => Ok(Autopilot::Smart),
+        Some("boring") => Ok(Autopilot::Boring),
+        Some(name) => Err(PyValueError::new_err(format!("unknown autopilot: {}", name))),
+    }
+}
+
+fn new_game(width: u32, height: u32, hex: bool, seed: Option<u64>) -> PyResult<RsGame> {
+    // the rows of the hex grid only wrap around with an even height
+    let height = if hex && height % 2 == 1 { height + 1 } else { height };
+    if width < 3 || height < 3 {
+        return Err(PyValueError::new_err("the board needs at least 3x3 tiles"));
+    }
+
+    let mut game = RsGame::new((width, height));
+    if hex {
+        game.set_grid(Grid::Hex);
+    }
+    if let Some(seed) = seed {
+        game.seed(seed);
+        game.restart();
+    }
+    Ok(game)
+}
+
+/// a game of snake, advanced round by round
+#[pyclass]
+struct Game {
+    game: RsGame,
+}
+
+#[pymethods]
+impl Game {
+    #[new]
+    #[pyo3(signature = (width=20, height=20, hex=false, seed=None))]
+    fn new(width: u32, height: u32, hex: bool, seed: Option<u64>) -> PyResult<Game> {
+        Ok(Game {
+            game: new_game(width, height, hex, seed)?,
+        })
+    }
+
+    /// start over, with a seed the food positions are reproducible
+    #[pyo3(signature = (seed=None))]
+    fn restart(&mut self, seed: Option<u64>) {
+        if let Some(seed) = seed {
+            self.game.seed(seed);
+        }
+        self.game.restart();
+    }
+
+    /// 0 goes straight, 1 turns left and 2 turns right
+    fn turn(&mut self, action: usize) -> PyResult<()> {
+        self.game.steer(turn(action)?);
+        Ok(())
+    }
+
+    /// advance by one round
+    fn tick(&mut self) {
+        self.game.tick();
+    }
+
+    /// let a built-in autopilot steer, `None` to steer yourself
+    #[pyo3(signature = (name=None))]
+    fn set_autopilot(&mut self, name: Option<&str>) -> PyResult<()> {
+        self.game.set_autopilot(autopilot(name)?);
+        Ok(())
+    }
+
+    /// the board encoded as `planes`, `egocentric` or `features`
+    #[pyo3(signature = (observation="planes"))]
+    fn observe<'py>(&self, py: Python<'py>, observation: &str) -> PyResult<Bound<'py, PyArray1<f32>>> {
+        Ok(self.game.observe(encoding(observation)?).into_pyarray(py))
+    }
+
+    /// one character per tile, as shown in the terminal
+    fn board<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<u8>>> {
+        let (w, h) = self.game.size();
+        let text = self.game.to_text();
+        let tiles: Vec<u8> = text.lines()
+                                 .take(h as usize)
+                                 .flat_map(|l| l.bytes())
+                                 .collect();
+        tiles.into_pyarray(py).reshape([h as usize, w as usize])
+    }
+
+    #[getter]
+    fn size(&self) -> (u32, u32) {
+        self.game.size()
+    }
+
+    #[getter]
+    fn score(&self) -> i64 {
+        self.game.score
+    }
+
+    #[getter]
+    fn round(&self) -> u64 {
+        self.game.round()
+    }
+
+    #[getter]
+    fn finished(&self) -> bool {
+        self.game.finished()
+    }
+
+    #[getter]
+    fn won(&self) -> bool {
+        self.game.won()
+    }
+
+    fn __str__(&self) -> String {
+        self.game.to_text()
+    }
+}
+
+/// reinforcement learning environment in the style of gym
+#[pyclass]
+struct Env {
+    env: RsEnv,
+}
+
+#[pymethods]
+impl Env {
+    #[new]
+    #[pyo3(signature = (width=20, height=20, hex=false, observation="features", rewards=None, autopilot=None))]
+    fn new(
+        width: u32,
+        height: u32,
+        hex: bool,
+        observation: &str,
+        rewards: Option<&Bound<'_, PyDict>>,
+        autopilot: Option<&str>,
+    ) -> PyResult<Env> {
+        let mut r = Rewards::default();
+        if let Some(rewards) = rewards {
+            for (key, value) in rewards.iter() {
+                let value: f64 = value.extract()?;
+                match key.extract::<String>()?.as_str() {
+                    "food" => r.food = value,
+                    "death" => r.death = value,
+                    "step" => r.step = value,
+                    "win" => r.win = value,
+                    k => return Err(PyValueError::new_err(format!("unknown reward: {}", k))),
+                }
+            }
+        }
+
+        let mut game = new_game(width, height, hex, None)?;
+        game.set_autopilot(self::autopilot(autopilot)?);
+        Ok(Env {
+            env: RsEnv::new(game, encoding(observation)?, r),
+        })
+    }
+
+    #[pyo3(signature = (seed=None))]
+    fn reset<'py>(&mut self, py: Python<'py>, seed: Option<u64>) -> Bound<'py, PyArray1<f32>> {
+        self.env.reset(seed).into_pyarray(py)
+    }
+
+    /// returns the observation, the reward, whether the episode is done and an info dict
+    fn step<'py>(
+        &mut self,
+        py: Python<'py>,
+        action: usize,
+    ) -> PyResult<Step<'py>> {
+        let (obs, reward, done, info) = self.env.step(turn(action)?);
+
+        let dict = PyDict::new(py);
+        dict.set_item("score", info.score)?;
+        dict.set_item("length", info.length)?;
+        dict.set_item("round", info.round)?;
+        dict.set_item("won", info.won)?;
+        dict.set_item("truncated", info.truncated)?;
+
+        Ok((obs.into_pyarray(py), reward, done, dict))
+    }
+
+    #[getter]
+    fn observation_len(&self) -> usize {
+        self.env.observation_len()
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "rsnake")]
+fn rsnake_python(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Game>()?;
+    m.add_class::<Env>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_board() {
+        let mut game = Game::new(3, 3, false, Some(1)).unwrap();
+        assert_eq!(game.__str__().lines().filter(|l| l.contains('@')).count(), 1);
+        game.turn(1).unwrap();
+        game.tick();
+        assert_eq!(game.__str__().lines().count(), 4);
+
+        let mut hex = Game::new(3, 3, true, Some(1)).unwrap();
+        hex.tick();
+        assert_eq!(hex.size(), (3, 4));
+    }
+}
@@ -0,0 +1,69 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// writer for the asciinema v2 format, every frame replaces the whole screen
+///
+/// The frames are kept until `finish`, since the header needs the size of the
+/// largest one, e.g., with the text at the end of the game.
+pub struct Asciicast {
+    out: BufWriter<File>,
+    frames: Vec<(f64, String)>,
+    size: (usize, usize),
+}
+
+impl Asciicast {
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Asciicast> {
+        Ok(Asciicast {
+            out: BufWriter::new(File::create(path)?),
+            frames: Vec::new(),
+            size: (0, 0),
+        })
+    }
+
+    /// `time` is the time since the start of the recording in seconds
+    pub fn frame(&mut self, time: f64, text: &str) {
+        let lines: Vec<&str> = text.split("\r\n").collect();
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        self.size = (self.size.0.max(width), self.size.1.max(lines.len()));
+        self.frames.push((time, text.to_string()));
+    }
+
+    /// write the header and all frames
+    pub fn finish(mut self) -> io::Result<()> {
+        let header = json!({
+            "version": 2,
+            "width": self.size.0,
+            "height": self.size.1,
+            "title": "rsnake",
+        });
+        writeln!(self.out, "{}", header)?;
+
+        for (time, text) in self.frames {
+            // move the cursor to the top left and clear the screen
+            let data = format!("\x1b[H\x1b[2J{}", text);
+            let event = json!([time, "o", data]);
+            writeln!(self.out, "{}", event)?;
+        }
+        self.out.flush()
+    }
+}
+
+#[test]
+fn test_size() {
+    use std::env;
+    use std::fs;
+
+    let path = env::temp_dir().join("rsnake_test_size.cast");
+    let mut cast = Asciicast::create(&path).unwrap();
+    cast.frame(0., "..@\r\nscore: 0");
+    cast.frame(0.2, "@..\r\nscore: 1 Game Over!\r\n1/2 Level");
+    cast.finish().unwrap();
+
+    let content = fs::read_to_string(&path).unwrap();
+    let header: ::serde_json::Value = ::serde_json::from_str(content.lines().next().unwrap()).unwrap();
+    assert_eq!(header["width"], 19);
+    assert_eq!(header["height"], 3);
+    assert_eq!(content.lines().count(), 3);
+    fs::remove_file(&path).unwrap();
+}
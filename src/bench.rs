@@ -0,0 +1,58 @@
+use std::time::Instant;
+
+use rand::{thread_rng, Rng};
+
+use game::Heatmap;
+use game::env::{Env, Rewards, Turn};
+use parse_cl::Options;
+use export::save_heatmap;
+use new_game;
+
+/// step the learning environment with random actions as fast as possible and report the speed,
+/// an autopilot overrides the random actions
+pub fn run(o: &Options, steps: u64) {
+    let mut env = Env::new(new_game(o), o.observation, Rewards::default());
+    let mut rng = thread_rng();
+
+    let mut episodes = 0;
+    let mut score = 0;
+    let mut rounds = 0;
+    let mut longest = 0;
+    let mut won = 0;
+    let mut truncated = 0;
+    let mut heatmap = Heatmap::new(env.game().size());
+
+    let start = Instant::now();
+    env.reset(o.seed);
+    for _ in 0..steps {
+        let turn = Turn::from_index(rng.gen_range(0, 3)).unwrap();
+        let (_, _, done, info) = env.step(turn);
+        heatmap.visit(env.game());
+        if done {
+            episodes += 1;
+            score += info.score;
+            rounds += info.round;
+            longest = longest.max(info.length);
+            won += info.won as u32;
+            truncated += info.truncated as u32;
+            env.reset(None);
+        }
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    println!("{} steps in {:.2} s, {:.0} steps/s, observations of {} values",
+             steps, elapsed, steps as f64 / elapsed, env.observation_len());
+    if episodes > 0 {
+        println!("{} episodes, mean score {:.2}, mean length {:.1} rounds, longest snake {}, won {}, truncated {}",
+                 episodes,
+                 score as f64 / episodes as f64,
+                 rounds as f64 / episodes as f64,
+                 longest,
+                 won,
+                 truncated);
+    }
+
+    if let Some(ref path) = o.heatmap {
+        save_heatmap(&heatmap, path, o.scale).expect("Could not save heatmap");
+    }
+}
@@ -0,0 +1,106 @@
+use rand::{Rng, SeedableRng, FromEntropy};
+use rand::rngs::SmallRng;
+
+use game::{Game, Grid, Mode, Autopilot, Network};
+use parse_cl::Options;
+
+/// number of networks per generation
+const POPULATION: usize = 100;
+/// the best networks survive unchanged
+const ELITE: usize = 10;
+/// games every network plays per generation, on the same boards for everyone
+const GAMES: usize = 3;
+/// fraction of the weights which mutate
+const RATE: f64 = 0.1;
+/// width of the mutations
+const SIGMA: f64 = 0.3;
+
+/// the game every network starts from, without key bindings, trace or saving the progress of a campaign
+fn new_board(o: &Options) -> Game {
+    let mut mode = o.mode.clone();
+    if let Mode::Campaign(ref mut c) = mode {
+        c.forget_progress();
+    }
+    let mut game = Game::with_mode(o.size, mode);
+    if o.hex {
+        game.set_grid(Grid::Hex);
+    }
+    game.set_difficulty(o.difficulty);
+    game
+}
+
+/// food eaten in the games, a bit of survival breaks ties
+fn fitness(board: &Game, net: &Network, seeds: &[u64]) -> f64 {
+    let mut fitness = 0.;
+    for &seed in seeds {
+        let mut game = board.clone();
+        game.seed(seed);
+        game.restart();
+        game.set_network(net.clone());
+        game.set_autopilot(Autopilot::Neural);
+
+        // a network which only runs in circles does not get anywhere
+        let (w, h) = game.size();
+        let max_idle = 2 * (w * h) as u64;
+        let mut idle = 0;
+        while !game.finished() && idle < max_idle {
+            let score = game.score;
+            game.tick();
+            idle = if game.score > score { 0 } else { idle + 1 };
+        }
+
+        fitness += game.score as f64 + 1e-4 * game.round() as f64;
+    }
+
+    fitness / seeds.len() as f64
+}
+
+/// the fitter of a few random networks
+fn tournament<'a, R: Rng>(scored: &'a [(f64, Network)], rng: &mut R) -> &'a Network {
+    let mut best = &scored[rng.gen_range(0, scored.len())];
+    for _ in 0..2 {
+        let other = &scored[rng.gen_range(0, scored.len())];
+        if other.0 > best.0 {
+            best = other;
+        }
+    }
+    &best.1
+}
+
+/// evolve the network of the neural autopilot with a genetic algorithm,
+/// the best network of every generation is saved to the genome file
+pub fn run(o: &Options, generations: u64) {
+    let mut rng = match o.seed {
+        Some(s) => SmallRng::seed_from_u64(s),
+        None => SmallRng::from_entropy(),
+    };
+
+    let board = new_board(o);
+    let outputs = board.grid().directions().len();
+    let mut population: Vec<Network> = (0..POPULATION).map(|_| Network::random(outputs, &mut rng))
+                                                      .collect();
+
+    for generation in 0..generations {
+        let seeds: Vec<u64> = (0..GAMES).map(|_| rng.gen()).collect();
+        let mut scored: Vec<(f64, Network)> = population.into_iter()
+                                                        .map(|net| (fitness(&board, &net, &seeds), net))
+                                                        .collect();
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+
+        let mean = scored.iter().map(|s| s.0).sum::<f64>() / scored.len() as f64;
+        println!("generation {}: best {:.2}, mean {:.2}", generation, scored[0].0, mean);
+        scored[0].1.save(&o.genome).expect("Could not save genome");
+
+        population = scored.iter()
+                           .take(ELITE)
+                           .map(|s| s.1.clone())
+                           .collect();
+        while population.len() < POPULATION {
+            let a = tournament(&scored, &mut rng);
+            let b = tournament(&scored, &mut rng);
+            let mut child = a.crossover(b, &mut rng);
+            child.mutate(RATE, SIGMA, &mut rng);
+            population.push(child);
+        }
+    }
+}
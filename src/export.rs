@@ -0,0 +1,153 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+use graphics::Context;
+use gif;
+use png;
+use png::HasParameters;
+
+use game::renderable::Renderable;
+use game::{Game, Heatmap};
+use raster::{Raster, RasterGlyphs};
+use asciicast::Asciicast;
+use parse_cl::Options;
+use {new_game, announce, fit_scale};
+
+enum Sink {
+    Frames(PathBuf, usize),
+    Gif(gif::Encoder<BufWriter<File>>),
+    Cast(Asciicast, f64),
+}
+
+impl Sink {
+    fn write(&mut self, game: &Game, raster: &Raster, delay: f64) -> io::Result<()> {
+        let (width, height) = raster.size();
+        match *self {
+            Sink::Frames(ref dir, ref mut n) => {
+                let file = File::create(dir.join(format!("frame_{:05}.png", n)))?;
+                let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+                encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+                let mut writer = encoder.write_header()?;
+                writer.write_image_data(raster.pixels())?;
+                *n += 1;
+            }
+            Sink::Gif(ref mut encoder) => {
+                let mut pixels = raster.pixels().to_vec();
+                let mut frame = gif::Frame::from_rgba_speed(width as u16, height as u16, &mut pixels, 10);
+                // gif delays are in units of 10 ms
+                frame.delay = (delay * 100.).round() as u16;
+                encoder.write_frame(&frame)?;
+            }
+            Sink::Cast(ref mut cast, ref mut time) => {
+                cast.frame(*time, &game.to_text());
+                *time += delay;
+            }
+        }
+        Ok(())
+    }
+
+    /// complete the file after the last frame
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Sink::Cast(cast, _) => cast.finish(),
+            Sink::Frames(..) | Sink::Gif(_) => Ok(()),
+        }
+    }
+}
+
+fn gif_sink<P: AsRef<Path>>(path: P, size: (u32, u32)) -> io::Result<Sink> {
+    // imported here, since it clashes with png::HasParameters
+    use gif::SetParameter;
+
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = gif::Encoder::new(file, size.0 as u16, size.1 as u16, &[])?;
+    encoder.set(gif::Repeat::Infinite)?;
+    Ok(Sink::Gif(encoder))
+}
+
+/// as png image if the file name ends in .png, else as csv
+pub fn save_heatmap(heatmap: &Heatmap, path: &str, scale: u32) -> io::Result<()> {
+    if !path.ends_with(".png") {
+        return heatmap.save_csv(path)
+    }
+
+    let (w, h) = heatmap.size();
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), w * scale, h * scale);
+    encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&heatmap.pixels(scale))?;
+    Ok(())
+}
+
+/// play `o.games` games without a window and write every round as an image or text
+pub fn run(o: &Options) -> io::Result<()> {
+    let mut game = new_game(o);
+    game.subscribe(announce);
+
+    let size = game.size();
+    let pixels = (size.0 * o.scale, size.1 * o.scale);
+
+    let mut sinks = Vec::new();
+    if let Some(ref dir) = o.export_frames {
+        fs::create_dir_all(dir)?;
+        sinks.push(Sink::Frames(PathBuf::from(dir), 0));
+    }
+    if let Some(ref path) = o.export_gif {
+        sinks.push(gif_sink(path, pixels)?);
+    }
+    // only rasterize if we need images
+    let images = !sinks.is_empty();
+    if let Some(ref path) = o.export_cast {
+        sinks.push(Sink::Cast(Asciicast::create(path)?, 0.));
+    }
+
+    let mut raster = Raster::new(pixels.0, pixels.1);
+    let mut glyphs = RasterGlyphs::new("assets/FiraSans-Regular.ttf")?;
+    let c = Context::new_abs(pixels.0 as f64, pixels.1 as f64);
+    let mut heatmap = Heatmap::new(size);
+
+    for n in 0..o.games {
+        // the random numbers continue, such that the games differ,
+        // but a seeded batch is reproducible, the trace goes on as well
+        if n > 0 {
+            game.restart();
+        }
+
+        loop {
+            if images {
+                let size = game.size();
+                game.render(c, &mut raster, size, fit_scale(size, pixels), &mut glyphs);
+            }
+
+            let last = game.finished() || o.rounds.map_or(false, |r| game.round() >= r);
+            // show the last frame a bit longer
+            let delay = if last { 2. } else { game.delay() };
+            for s in sinks.iter_mut() {
+                s.write(&game, &raster, delay)?;
+            }
+
+            heatmap.visit(&game);
+            if last {
+                break
+            }
+            game.tick();
+        }
+    }
+
+    for s in sinks {
+        s.finish()?;
+    }
+
+    if let Some(ref path) = o.heatmap {
+        save_heatmap(&heatmap, path, o.scale)?;
+    }
+
+    // the last game
+    if let Some(ref path) = o.record {
+        game.replay().save(path)?;
+    }
+
+    Ok(())
+}
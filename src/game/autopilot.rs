@@ -1,22 +1,70 @@
 use super::Game;
-use super::orientation::{State, Direction, Reachable, best_first_search};
-use rand::{thread_rng, Rng};
+use super::orientation::{Point, State, Direction, Grid, Reachable, best_first_search, flood_fill};
+use rand::Rng;
 
-#[derive(Hash, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Hash, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum Autopilot {
     Stupid,
     Smart,
     Boring,
+    /// an evolved neural network, see `Network`
+    Neural,
+    /// a table learned by q-learning, see `QTable`
+    QLearning,
+    /// monte carlo tree search over simulated games
+    Mcts,
     None,
 }
 
 impl Game {
+    /// the food, or the entry of the first portal on a shorter way to it
+    fn waypoint(&self) -> Point {
+        let head = self.snake.head();
+        let food = *self.map.get_food();
+        let portals = self.map.get_portals();
+        let entries: Vec<Point> = portals.keys().cloned().collect();
+
+        // distance to every portal and the first portal on the way there,
+        // which might be a detour through other portals
+        let mut dist: Vec<(i32, Point)> = entries.iter()
+                                                 .map(|e| (self.map.distance(head, e), *e))
+                                                 .collect();
+        for _ in 0..entries.len() {
+            for i in 0..entries.len() {
+                let exit = portals[&entries[i]];
+                for j in 0..entries.len() {
+                    let d = dist[i].0 + 1 + self.map.distance(&exit, &entries[j]);
+                    if d < dist[j].0 {
+                        dist[j] = (d, dist[i].1);
+                    }
+                }
+            }
+        }
+
+        let mut best = (self.map.distance(head, &food), food);
+        for (entry, &(d, first)) in entries.iter().zip(dist.iter()) {
+            let d = d + 1 + self.map.distance(&portals[entry], &food);
+            if d < best.0 {
+                best = (d, first);
+            }
+        }
+
+        best.1
+    }
+
     fn find_food(&mut self) {
+        if self.map.grid != Grid::Square {
+            return self.find_food_greedy()
+        }
+
         // take shortest way to food (manhattan metric)
         let original = self.snake.direction;
+        let target = self.waypoint();
+        self.note_branch("find_food");
+        self.note(|_, d| d.target = Some(target));
 
-        let mut dx = self.map.get_food().x - self.snake.head().x;
-        let mut dy = self.map.get_food().y - self.snake.head().y;
+        let mut dx = target.x - self.snake.head().x;
+        let mut dy = target.y - self.snake.head().y;
 
         // get shortest way over periodic boundaries
         if dx.abs() as f64 > self.map.size.0 as f64 / 2. {
@@ -44,6 +92,32 @@ impl Game {
             }
         }
 
+        // ensure that we do not walk into a wall
+        let direction = self.snake.direction;
+        self.note_candidate(direction, None, None);
+        if self.detect_hazard() {
+            self.snake.turn(original)
+        }
+    }
+
+    /// step onto the neighbor closest to the food, works on every grid
+    fn find_food_greedy(&mut self) {
+        let original = self.snake.direction;
+        let target = self.waypoint();
+        let head = *self.snake.head();
+        self.note_branch("find_food");
+        self.note(|_, d| d.target = Some(target));
+
+        let best = self.map.grid.directions()
+                                .iter()
+                                .filter(|&&d| !self.snake.reverse(d))
+                                .min_by_key(|&&d| self.map.distance(&(head + d), &target))
+                                .cloned();
+        if let Some(d) = best {
+            self.snake.turn(d);
+            self.note_candidate(d, None, None);
+        }
+
         // ensure that we do not walk into a wall
         if self.detect_hazard() {
             self.snake.turn(original)
@@ -53,6 +127,7 @@ impl Game {
     fn find_food_top_right(&mut self) {
         // take shortest way to food (manhattan metric)
         let original = self.snake.direction;
+        self.note_branch("find_food_top_right");
 
         let dx = self.map.get_food().x - self.snake.head().x;
         let dy = self.map.get_food().y - self.snake.head().y;
@@ -77,14 +152,22 @@ impl Game {
         let original = self.snake.direction;
 
         self.snake.turn(Direction::N);
+        self.note_branch("go_top");
+        self.note_candidate(Direction::N, None, None);
 
         if self.detect_hazard() {
             self.snake.turn(original)
         }
     }
 
-    fn detect_hazard(&self) -> bool {
-        match self.map.at(&self.snake.peek()) {
+    /// whether the snake would collide in the next round, including hazards which move there
+    pub fn detect_hazard(&self) -> bool {
+        self.hazard(self.snake.direction)
+    }
+
+    /// whether the snake would collide in the next round when moving in direction `dir`
+    pub fn hazard(&self, dir: Direction) -> bool {
+        match self.map.predict(&self.map.neighbor(self.snake.head(), dir), 1) {
             State::Empty | State::Food => false,
             _ => true
         }
@@ -94,18 +177,28 @@ impl Game {
         let mut decision = false;
         if self.detect_hazard() {
             decision = true;
+            self.note_branch("avoid_hazard");
             // decide to not collide in the next step
-            let mut rng = thread_rng();
-            let left = rng.gen::<f64>() > 0.5;
+            let original = self.snake.direction;
+            let left = self.map.rng().gen::<f64>() > 0.5;
             if left {
                 self.snake.turn_left();
             } else {
                 self.snake.turn_right();
             }
+            let direction = self.snake.direction;
+            self.note_candidate(direction, None, None);
 
+            // try the other side
             if self.detect_hazard() {
-                self.snake.turn_right();
-                self.snake.turn_right();
+                self.snake.turn(original);
+                if left {
+                    self.snake.turn_right();
+                } else {
+                    self.snake.turn_left();
+                }
+                let direction = self.snake.direction;
+                self.note_candidate(direction, None, None);
             }
         }
 
@@ -116,6 +209,7 @@ impl Game {
         let mut decision = false;
         if self.detect_hazard() {
             decision = true;
+            self.note_branch("avoid_hazard_top_right");
             // decide to not collide in the next step
             if self.snake.direction == Direction::E {
                 self.snake.turn(Direction::N);
@@ -129,11 +223,30 @@ impl Game {
 
     fn occupied_neighbors(&self) -> usize {
         let p = self.snake.head();
-        p.neighbors2()
-         .filter(|x| self.map.at(x) != State::Empty && self.map.at(x) != State::Food)
+        let neighbors = match self.map.grid {
+            Grid::Square => p.neighbors2(),
+            Grid::Hex => p.hex_neighbors(),
+        };
+        neighbors
+         .filter(|x| match self.map.predict(x, 1) {
+             State::Empty | State::Food | State::Portal => false,
+             _ => true
+         })
          .count()
     }
 
+    /// whether the current direction collides or cuts the snake off from the end of its tail
+    fn trapped(&mut self) -> bool {
+        let direction = self.snake.direction;
+        if self.detect_hazard() {
+            self.note_candidate(direction, None, None);
+            return true
+        }
+        let reachable = best_first_search(&self.snake.peek(&self.map), self.snake.end(), &self.map);
+        self.note_candidate(direction, Some(reachable), None);
+        reachable == Reachable::No
+    }
+
     fn avoid_trapping(&mut self) -> bool {
         let mut decision = false;
         if self.occupied_neighbors() <= 1 {
@@ -145,15 +258,14 @@ impl Game {
 
         // test if we can reach the end of our tail and the food
         // if we can, we can go on forever
-        if self.detect_hazard()
-            || best_first_search(&self.snake.peek(), self.snake.end(), &self.map) == Reachable::No
-        {
+        if self.trapped() {
+            self.note_branch("avoid_trapping");
             self.snake.turn_left();
-            if self.detect_hazard()
-                || best_first_search(&self.snake.peek(), self.snake.end(), &self.map) == Reachable::No
-            {
-                self.snake.turn_left();
-                self.snake.turn_left();
+            if self.trapped() {
+                self.snake.turn(original);
+                self.snake.turn_right();
+                let direction = self.snake.direction;
+                self.note_candidate(direction, None, None);
             }
             decision = true;
         }
@@ -169,6 +281,52 @@ impl Game {
         decision
     }
 
+    /// tiles reachable after moving in direction `dir`, at most as many as the snake is long
+    pub fn space(&self, dir: Direction) -> usize {
+        let next = self.map.neighbor(self.snake.head(), dir);
+        flood_fill(&next, &self.map, self.snake.length)
+    }
+
+    /// turn away from a region, which is too small to hold the whole snake,
+    /// into the largest region around
+    fn avoid_small_regions(&mut self) -> bool {
+        let length = self.snake.length;
+        let current = self.space(self.snake.direction);
+        if current >= length {
+            return false
+        }
+
+        let spaces: Vec<(usize, Direction)> = self.map.grid.directions()
+                                                  .iter()
+                                                  .filter(|&&d| !self.snake.reverse(d))
+                                                  .map(|&d| (self.space(d), d))
+                                                  .collect();
+        for &(s, d) in &spaces {
+            self.note_candidate(d, None, Some(s));
+        }
+        match spaces.into_iter().max_by_key(|&(s, _)| s) {
+            Some((s, d)) if s > current => {
+                self.note_branch("avoid_small_regions");
+                self.snake.turn(d);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// let `autopilot` decide the direction for the next round
+    pub fn steer_with(&mut self, autopilot: Autopilot) {
+        match autopilot {
+            Autopilot::Stupid => self.stupid_autopilot(),
+            Autopilot::Smart => self.smart_autopilot(),
+            Autopilot::Boring => self.boring_autopilot(),
+            Autopilot::Neural => self.neural_autopilot(),
+            Autopilot::QLearning => self.q_autopilot(),
+            Autopilot::Mcts => self.mcts_autopilot(),
+            Autopilot::None => (),
+        }
+    }
+
     pub fn stupid_autopilot(&mut self) {
         if !self.avoid_hazard() {
             self.find_food()
@@ -179,9 +337,15 @@ impl Game {
         if !self.avoid_trapping() {
             self.find_food();
         }
+        self.avoid_small_regions();
     }
 
     pub fn boring_autopilot(&mut self) {
+        // the pattern needs north and east
+        if self.map.grid != Grid::Square {
+            return self.smart_autopilot()
+        }
+
         if !self.avoid_hazard_top_right() {
             if self.snake.length as u32 > self.map.size.1 {
                 self.go_top();
@@ -191,3 +355,33 @@ impl Game {
         }
     }
 }
+
+#[test]
+fn test_avoid_small_regions() {
+    // the snake fills its row except for two tiles
+    let mut game = Game::new((10, 16));
+    game.set_autopilot(Autopilot::None);
+    let food = Point::new(0, game.snake.head().y + 8);
+    game.map.place_food(food);
+    for _ in 0..5 {
+        game.snake.feed();
+        game.tick();
+    }
+    let length = game.snake.length;
+    assert_eq!(length, 8);
+
+    // walls turn the two tiles into a pocket with the food, which reaches the end of the tail,
+    // such that the snake is not trapped, but would be after eating
+    let head = *game.snake.head();
+    let at = |dx: i32, dy: i32| Point::new(head.x + dx, head.y + dy);
+    for &(dx, dy) in &[(1, -1), (2, -1), (1, 1), (2, 1)] {
+        game.map.add_wall(at(dx, dy));
+    }
+    game.map.place_food(at(2, 0));
+    assert!(game.space(Direction::E) < length);
+
+    game.smart_autopilot();
+    let direction = game.snake.direction;
+    assert!(direction == Direction::N || direction == Direction::S);
+    assert!(game.space(direction) >= length);
+}
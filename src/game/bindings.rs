@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use dirs;
+use toml;
+use piston::input::keyboard::Key;
+
+#[derive(Hash, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    SpeedUp,
+    SlowDown,
+    StupidAutopilot,
+    SmartAutopilot,
+    BoringAutopilot,
+    Manual,
+    Pause,
+    Restart,
+}
+
+/// all actions in the order they appear in the help,
+/// with their name in the config file and their description
+const ACTIONS: &[(Action, &str, &str)] = &[
+    (Action::Restart, "restart", "Restart"),
+    (Action::SpeedUp, "speed_up", "Speed up"),
+    (Action::SlowDown, "slow_down", "Slow down"),
+    (Action::Up, "up", "Up"),
+    (Action::Left, "left", "Left"),
+    (Action::Down, "down", "Down"),
+    (Action::Right, "right", "Right"),
+    (Action::StupidAutopilot, "stupid_autopilot", "Stupid Autopilot"),
+    (Action::SmartAutopilot, "smart_autopilot", "Smart Autopilot"),
+    (Action::BoringAutopilot, "boring_autopilot", "Boring Autopilot"),
+    (Action::Manual, "manual", "Manual Control"),
+    (Action::Pause, "pause", "Pause and Help"),
+];
+
+const STEERING: [Action; 4] = [Action::Up, Action::Left, Action::Down, Action::Right];
+
+/// names of the keys which can be used in the config file
+const KEYS: &[(&str, Key)] = &[
+    ("A", Key::A), ("B", Key::B), ("C", Key::C), ("D", Key::D), ("E", Key::E),
+    ("F", Key::F), ("G", Key::G), ("H", Key::H), ("I", Key::I), ("J", Key::J),
+    ("K", Key::K), ("L", Key::L), ("M", Key::M), ("N", Key::N), ("O", Key::O),
+    ("P", Key::P), ("Q", Key::Q), ("R", Key::R), ("S", Key::S), ("T", Key::T),
+    ("U", Key::U), ("V", Key::V), ("W", Key::W), ("X", Key::X), ("Y", Key::Y),
+    ("Z", Key::Z),
+    ("0", Key::D0), ("1", Key::D1), ("2", Key::D2), ("3", Key::D3), ("4", Key::D4),
+    ("5", Key::D5), ("6", Key::D6), ("7", Key::D7), ("8", Key::D8), ("9", Key::D9),
+    ("Up", Key::Up), ("Down", Key::Down), ("Left", Key::Left), ("Right", Key::Right),
+    ("Space", Key::Space), ("Return", Key::Return), ("Tab", Key::Tab), ("Backspace", Key::Backspace),
+];
+
+pub fn parse_key(name: &str) -> Option<Key> {
+    KEYS.iter()
+        .find(|&&(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|&(_, k)| k)
+}
+
+fn key_name(key: Key) -> &'static str {
+    KEYS.iter()
+        .find(|&&(_, k)| k == key)
+        .map(|&(n, _)| n)
+        .unwrap_or("?")
+}
+
+#[derive(Deserialize)]
+struct Config {
+    #[serde(default)]
+    keys: HashMap<String, Vec<String>>,
+}
+
+/// maps keys to the actions they trigger
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    keys: HashMap<Action, Vec<Key>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        use piston::input::keyboard::Key::*;
+
+        let mut keys = HashMap::new();
+        keys.insert(Action::Up, vec![W, Up]);
+        keys.insert(Action::Down, vec![S, Down]);
+        keys.insert(Action::Left, vec![A, Left]);
+        keys.insert(Action::Right, vec![D, Right]);
+        keys.insert(Action::SpeedUp, vec![E]);
+        keys.insert(Action::SlowDown, vec![Q]);
+        keys.insert(Action::StupidAutopilot, vec![F]);
+        keys.insert(Action::SmartAutopilot, vec![T]);
+        keys.insert(Action::BoringAutopilot, vec![G]);
+        keys.insert(Action::Manual, vec![M]);
+        keys.insert(Action::Pause, vec![P, H]);
+        keys.insert(Action::Restart, vec![R]);
+
+        Bindings {
+            keys
+        }
+    }
+}
+
+impl Bindings {
+    /// `$XDG_CONFIG_HOME/rsnake/config.toml`
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("rsnake").join("config.toml"))
+    }
+
+    /// load the bindings from a config file, missing actions keep their default keys
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Bindings> {
+        let content = fs::read_to_string(path)?;
+        Bindings::from_toml(&content)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn from_toml(content: &str) -> Result<Bindings, String> {
+        let config: Config = toml::from_str(content).map_err(|e| e.to_string())?;
+
+        let mut bindings = Bindings::default();
+        for (name, keys) in config.keys {
+            let action = ACTIONS.iter()
+                                .find(|&&(_, n, _)| n == name)
+                                .map(|&(a, _, _)| a)
+                                .ok_or_else(|| format!("unknown action '{}'", name))?;
+            let keys = keys.iter()
+                           .map(|k| parse_key(k).ok_or_else(|| format!("unknown key '{}'", k)))
+                           .collect::<Result<Vec<Key>, String>>()?;
+            bindings.keys.insert(action, keys);
+        }
+
+        // every key may only trigger one action
+        for &(a, name, _) in ACTIONS {
+            for &(b, other, _) in ACTIONS.iter().filter(|&&(b, _, _)| b != a) {
+                if let Some(k) = bindings.keys[&a].iter().find(|k| bindings.keys[&b].contains(k)) {
+                    return Err(format!("key '{}' is bound to '{}' and '{}'", key_name(*k), name, other))
+                }
+            }
+        }
+
+        Ok(bindings)
+    }
+
+    pub fn action(&self, key: Key) -> Option<Action> {
+        ACTIONS.iter()
+               .map(|&(a, _, _)| a)
+               .find(|a| self.keys[a].contains(&key))
+    }
+
+    fn names(&self, action: Action) -> String {
+        let names: Vec<&str> = self.keys[&action].iter()
+                                                 .map(|&k| key_name(k))
+                                                 .collect();
+        names.join("/")
+    }
+
+    /// description of all bindings, the steering keys are combined into one line
+    pub fn help_texts(&self) -> Vec<String> {
+        let mut texts = Vec::new();
+        for &(action, _, description) in ACTIONS {
+            if action == Action::Up {
+                let first: Vec<&str> = STEERING.iter()
+                                               .filter_map(|a| self.keys[a].first())
+                                               .map(|&k| key_name(k))
+                                               .collect();
+                let separator = if first.iter().all(|n| n.len() == 1) { "" } else { "/" };
+                texts.push(format!("{}: Steer", first.join(separator)));
+            } else if STEERING.contains(&action) {
+                continue
+            } else {
+                texts.push(format!("{}: {}", self.names(action), description));
+            }
+        }
+        texts.push("Esc: Exit".to_string());
+
+        texts
+    }
+}
+
+#[test]
+fn test_from_toml() {
+    let b = Bindings::from_toml("[keys]\nup = [\"I\"]\nleft = [\"J\"]\ndown = [\"K\"]\nright = [\"L\"]").unwrap();
+    assert_eq!(b.action(Key::I), Some(Action::Up));
+    assert_eq!(b.action(Key::W), None);
+    assert_eq!(b.action(Key::R), Some(Action::Restart));
+    assert_eq!(b.help_texts()[3], "IJKL: Steer");
+
+    assert!(Bindings::from_toml("[keys]\nup = [\"R\"]").is_err());
+    assert!(Bindings::from_toml("[keys]\njump = [\"J\"]").is_err());
+    assert!(Bindings::from_toml("[keys]\nup = [\"Foo\"]").is_err());
+}
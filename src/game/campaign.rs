@@ -0,0 +1,369 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use dirs;
+
+use super::map::Map;
+use super::snake::Snake;
+use super::orientation::{Point, Grid};
+use super::hazard::Hazard;
+
+/// what needs to be achieved to finish a level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    Length(usize),
+    Score(i64),
+}
+
+impl Goal {
+    pub fn reached(&self, length: usize, score: i64) -> bool {
+        match *self {
+            Goal::Length(l) => length >= l,
+            Goal::Score(s) => score >= s,
+        }
+    }
+}
+
+/// a single level of the campaign
+///
+/// Levels are text files, starting with a header of `key = value` lines,
+/// followed by an empty line and the arena, where `#` is a wall and `S` the
+/// head of the snake, which starts heading east. The two tiles marked with the
+/// same digit are a pair of portals. `patrol` (waypoints) and `blink`
+/// (position and period in rounds) add moving hazards and may be repeated.
+///
+/// ```text
+/// name = The Box
+/// goal = length 15
+/// time = 120
+/// delay = 0.15
+/// patrol = 3,4 3,10
+/// blink = 5,5 10
+///
+/// ####################
+/// #   S              #
+/// ...
+/// ```
+#[derive(Debug, Clone)]
+pub struct Level {
+    pub name: String,
+    pub size: (u32, u32),
+    pub walls: Vec<Point>,
+    pub portals: Vec<(Point, Point)>,
+    pub hazards: Vec<Hazard>,
+    pub start: Point,
+    pub goal: Goal,
+    /// in seconds of game time
+    pub time_limit: Option<f64>,
+    pub delay: f64,
+}
+
+fn parse_point(s: &str) -> Result<Point, String> {
+    let coords: Vec<i32> = s.split(',')
+                            .map(|c| c.trim().parse::<i32>())
+                            .collect::<Result<_, _>>()
+                            .map_err(|_| format!("expected a position as x,y, got '{}'", s))?;
+    if coords.len() != 2 {
+        return Err(format!("expected a position as x,y, got '{}'", s))
+    }
+    Ok(Point::new(coords[0], coords[1]))
+}
+
+impl Level {
+    pub fn parse(content: &str) -> Result<Level, String> {
+        let mut lines = content.lines();
+
+        let mut name = String::from("Unnamed");
+        let mut goal = None;
+        let mut time_limit = None;
+        let mut delay = 0.2;
+        let mut hazards = Vec::new();
+        for line in lines.by_ref().take_while(|l| !l.trim().is_empty()) {
+            let mut kv = line.splitn(2, '=').map(|s| s.trim());
+            let key = kv.next().unwrap_or("");
+            let value = kv.next().ok_or_else(|| format!("expected 'key = value', got '{}'", line))?;
+            match key {
+                "name" => name = value.to_string(),
+                "goal" => {
+                    let mut words = value.split_whitespace();
+                    let kind = words.next().unwrap_or("");
+                    let n = words.next()
+                                 .and_then(|n| n.parse::<usize>().ok())
+                                 .ok_or_else(|| format!("goal needs a number: '{}'", value))?;
+                    goal = match kind {
+                        "length" => Some(Goal::Length(n)),
+                        "score" => Some(Goal::Score(n as i64)),
+                        _ => return Err(format!("unknown goal '{}'", kind)),
+                    };
+                }
+                "time" => time_limit = Some(value.parse::<f64>().map_err(|_| "time needs to be a number")?),
+                "delay" => delay = value.parse::<f64>().map_err(|_| "delay needs to be a number")?,
+                "patrol" => {
+                    let waypoints = value.split_whitespace()
+                                         .map(parse_point)
+                                         .collect::<Result<Vec<Point>, String>>()?;
+                    if waypoints.is_empty() {
+                        return Err("patrol needs at least one waypoint".to_string())
+                    }
+                    hazards.push(Hazard::patrol(&waypoints));
+                }
+                "blink" => {
+                    let mut words = value.split_whitespace();
+                    let p = parse_point(words.next().unwrap_or(""))?;
+                    let period = words.next()
+                                      .and_then(|n| n.parse::<u64>().ok())
+                                      .ok_or_else(|| format!("blink needs a period: '{}'", value))?;
+                    hazards.push(Hazard::Blinking(p, period));
+                }
+                _ => return Err(format!("unknown key '{}'", key)),
+            }
+        }
+
+        let rows: Vec<&str> = lines.collect();
+        let width = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0) as u32;
+        let height = rows.len() as u32;
+        if width == 0 || height == 0 {
+            return Err("the arena is empty".to_string())
+        }
+
+        let mut walls = Vec::new();
+        let mut ends: BTreeMap<char, Vec<Point>> = BTreeMap::new();
+        let mut start = None;
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let p = Point::new(x as i32, y as i32);
+                match c {
+                    '#' => walls.push(p),
+                    'S' => start = Some(p),
+                    '0'..='9' => ends.entry(c).or_insert_with(Vec::new).push(p),
+                    ' ' | '.' => (),
+                    _ => return Err(format!("unknown tile '{}'", c)),
+                }
+            }
+        }
+
+        let mut portals = Vec::new();
+        for (c, e) in ends {
+            if e.len() != 2 {
+                return Err(format!("portal '{}' needs exactly two ends, got {}", c, e.len()))
+            }
+            portals.push((e[0], e[1]));
+        }
+
+        Ok(Level {
+            name,
+            size: (width, height),
+            walls,
+            portals,
+            hazards,
+            start: start.unwrap_or_else(|| Point::new(7, 5)),
+            goal: goal.ok_or("the level needs a goal")?,
+            time_limit,
+            delay,
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Level> {
+        let content = fs::read_to_string(path)?;
+        Level::parse(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// whether the level can be played on `grid`, the snake and the hazards need to be inside the arena
+    pub fn validate(&self, grid: Grid) -> Result<(), String> {
+        let (w, h) = (self.size.0 as i32, self.size.1 as i32);
+        let inside = |p: &Point| p.x >= 0 && p.y >= 0 && p.x < w && p.y < h;
+
+        if !inside(&self.start) {
+            return Err(format!("the start {},{} is outside of the {}x{} arena, mark it with 'S'", self.start.x, self.start.y, w, h))
+        }
+        for hazard in &self.hazards {
+            let outside = match *hazard {
+                Hazard::Patrol(ref path) => path.iter().find(|p| !inside(p)).cloned(),
+                Hazard::Blinking(p, _) => Some(p).filter(|p| !inside(p)),
+            };
+            if let Some(p) = outside {
+                return Err(format!("the hazard at {},{} is outside of the {}x{} arena", p.x, p.y, w, h))
+            }
+        }
+
+        let blocked = |p: &Point| self.walls.contains(p) || self.portals.iter().any(|&(a, b)| a == *p || b == *p);
+        if Snake::starting_at(self.size, self.start).get_tail().iter().any(blocked) {
+            return Err("the snake starts on a wall or a portal".to_string())
+        }
+
+        // the rows of the hex grid only wrap around with an even height
+        if grid == Grid::Hex && h % 2 == 1 {
+            return Err(format!("the height of the arena needs to be even on the hex grid, but it is {}", h))
+        }
+        Ok(())
+    }
+
+    /// the arena and the snake at the start of the level
+    pub fn board(&self) -> (Map, Snake) {
+        let mut map = Map::new(self.size);
+        for w in &self.walls {
+            map.add_wall(*w);
+        }
+        for &(a, b) in &self.portals {
+            map.add_portals(a, b);
+        }
+        for h in &self.hazards {
+            map.add_hazard(h.clone());
+        }
+        let snake = Snake::starting_at(self.size, self.start);
+        map.init_snake(snake.get_tail().iter());
+
+        (map, snake)
+    }
+}
+
+/// an ordered list of levels, the progress is saved between sessions
+#[derive(Debug, Clone)]
+pub struct Campaign {
+    levels: Vec<Level>,
+    current: usize,
+    progress_file: Option<PathBuf>,
+}
+
+impl Campaign {
+    /// the given levels, starting with the first, without saving the progress
+    pub fn new(levels: Vec<Level>) -> Campaign {
+        Campaign {
+            levels,
+            current: 0,
+            progress_file: None,
+        }
+    }
+
+    /// all level files in a directory, in alphabetical order, which have to be playable on `grid`
+    pub fn load<P: AsRef<Path>>(dir: P, grid: Grid) -> io::Result<Campaign> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+                                         .filter_map(|e| e.ok())
+                                         .map(|e| e.path())
+                                         .filter(|p| p.is_file())
+                                         .collect();
+        paths.sort();
+
+        let levels = paths.iter()
+                          .map(|p| {
+                              let level = Level::load(p)?;
+                              level.validate(grid)
+                                   .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", p.display(), e)))?;
+                              Ok(level)
+                          })
+                          .collect::<io::Result<Vec<Level>>>()?;
+        if levels.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "no levels found"))
+        }
+
+        let progress_file = dirs::data_dir().map(|d| d.join("rsnake").join("campaign"));
+        let current = progress_file.as_ref()
+                                   .and_then(|p| fs::read_to_string(p).ok())
+                                   .and_then(|s| s.trim().parse::<usize>().ok())
+                                   .filter(|&c| c < levels.len())
+                                   .unwrap_or(0);
+
+        Ok(Campaign {
+            levels,
+            current,
+            progress_file,
+        })
+    }
+
+    pub fn level(&self) -> &Level {
+        &self.levels[self.current]
+    }
+
+    pub fn levels(&self) -> &[Level] {
+        &self.levels
+    }
+
+    pub fn number(&self) -> usize {
+        self.current + 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// play level `number`, counted from 1, without touching the saved progress, e.g., for a replay
+    pub fn replay_level(&mut self, number: usize) -> io::Result<()> {
+        if number == 0 || number > self.levels.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("there is no level {}", number)))
+        }
+        self.current = number - 1;
+        self.forget_progress();
+        Ok(())
+    }
+
+    /// do not remember the progress, e.g., for simulated games
+    pub fn forget_progress(&mut self) {
+        self.progress_file = None;
+    }
+
+    /// go to the next level and remember it, returns false if this was the last level
+    pub fn advance(&mut self) -> bool {
+        if self.current + 1 >= self.levels.len() {
+            // start from the beginning next time
+            self.save_progress(0);
+            return false
+        }
+        self.current += 1;
+        let current = self.current;
+        self.save_progress(current);
+        true
+    }
+
+    fn save_progress(&self, level: usize) {
+        if let Some(ref path) = self.progress_file {
+            let saved = path.parent()
+                            .map_or(Ok(()), fs::create_dir_all)
+                            .and_then(|_| fs::write(path, format!("{}\n", level)));
+            if let Err(e) = saved {
+                eprintln!("Could not save the campaign progress: {}", e);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_validate() {
+    let level = |header: &str, arena: &str| Level::parse(&format!("goal = length 5\n{}\n{}", header, arena)).unwrap();
+
+    let small = level("", "....\n....\n....");
+    // the snake would start at 7,5
+    assert!(small.validate(Grid::Square).is_err());
+
+    let odd = level("", "..S.\n....\n....");
+    assert!(odd.validate(Grid::Square).is_ok());
+    assert!(odd.validate(Grid::Hex).is_err());
+
+    assert!(level("patrol = 0,1 4,1\n", "..S.\n....").validate(Grid::Hex).is_err());
+    assert!(level("blink = 3,1 5\n", "..S.\n....").validate(Grid::Hex).is_ok());
+    assert!(level("", "#.S.\n....").validate(Grid::Square).is_err());
+
+    for &grid in &[Grid::Square, Grid::Hex] {
+        assert!(Campaign::load("assets/levels", grid).is_ok());
+    }
+}
+
+#[test]
+fn test_full_board() {
+    use super::{Game, Mode, Autopilot};
+
+    // the portals do not leave room for more food, such that the board is full earlier
+    let level = Level::parse("goal = length 20
+
+S...
+1..1").unwrap();
+    let mut game = Game::with_mode((4, 2), Mode::Campaign(Campaign::new(vec![level])));
+    game.seed(1);
+    game.restart();
+    game.set_autopilot(Autopilot::Smart);
+    for _ in 0..100 {
+        game.tick();
+    }
+    assert!(game.won());
+}
@@ -0,0 +1,72 @@
+/// do not try to catch up more than this many rounds at once, e.g., after the window was frozen
+const MAX_TICKS_PER_UPDATE: u32 = 5;
+
+/// fixed timestep scheduler
+///
+/// accumulates the elapsed time and hands it out in rounds of constant length `delay`
+#[derive(Debug, Clone)]
+pub struct Clock {
+    delay: f64,
+    accumulator: f64,
+}
+
+impl Clock {
+    pub fn new(delay: f64) -> Clock {
+        Clock {
+            delay,
+            accumulator: 0.,
+        }
+    }
+
+    /// add the elapsed time and return how many rounds are due
+    pub fn advance(&mut self, dt: f64) -> u32 {
+        self.accumulator += dt;
+
+        let mut ticks = 0;
+        while self.accumulator >= self.delay {
+            self.accumulator -= self.delay;
+            ticks += 1;
+        }
+
+        if ticks > MAX_TICKS_PER_UPDATE {
+            self.accumulator = 0.;
+            ticks = MAX_TICKS_PER_UPDATE;
+        }
+
+        ticks
+    }
+
+    /// fraction of the current round which has already passed, in [0, 1)
+    pub fn alpha(&self) -> f64 {
+        self.accumulator / self.delay
+    }
+
+    pub fn delay(&self) -> f64 {
+        self.delay
+    }
+
+    /// change the length of a round, keeping the progress within the current round
+    pub fn set_delay(&mut self, delay: f64) {
+        self.accumulator *= delay / self.delay;
+        self.delay = delay;
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulator = 0.;
+    }
+}
+
+#[test]
+fn test_clock() {
+    let mut clock = Clock::new(0.2);
+    assert_eq!(clock.advance(0.1), 0);
+    assert!((clock.alpha() - 0.5).abs() < 1e-9);
+    assert_eq!(clock.advance(0.35), 2);
+    assert!((clock.alpha() - 0.25).abs() < 1e-9);
+
+    clock.set_delay(0.1);
+    assert!((clock.alpha() - 0.25).abs() < 1e-9);
+
+    assert_eq!(clock.advance(100.), MAX_TICKS_PER_UPDATE);
+    assert_eq!(clock.alpha(), 0.);
+}
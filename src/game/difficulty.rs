@@ -0,0 +1,41 @@
+/// the snake never gets faster than this
+const MIN_DELAY: f64 = 0.02;
+
+/// how the delay between two rounds shrinks with the score
+#[derive(Hash, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    /// the speed only changes manually
+    Constant,
+    /// every food shortens the delay by 5 ms
+    Linear,
+    /// every `n` foods the game speeds up by the same factor as the speed up key
+    Stepped(u32),
+    /// every food shortens the delay by 3 percent
+    Exponential,
+}
+
+impl Difficulty {
+    /// the delay for a given score, starting at `base`
+    pub fn delay(&self, base: f64, score: i64) -> f64 {
+        let score = score.max(0);
+        let delay = match *self {
+            Difficulty::Constant => base,
+            Difficulty::Linear => base - 0.005 * score as f64,
+            Difficulty::Stepped(n) => base * 0.8f64.powi((score / n.max(1) as i64) as i32),
+            Difficulty::Exponential => base * 0.97f64.powi(score as i32),
+        };
+
+        // do not slow down the game if it was manually sped up already
+        delay.max(MIN_DELAY.min(base))
+    }
+}
+
+#[test]
+fn test_delay() {
+    assert_eq!(Difficulty::Constant.delay(0.2, 100), 0.2);
+    assert!((Difficulty::Linear.delay(0.2, 10) - 0.15).abs() < 1e-9);
+    assert_eq!(Difficulty::Linear.delay(0.2, 1000), MIN_DELAY);
+    assert_eq!(Difficulty::Stepped(5).delay(0.2, 4), 0.2);
+    assert!((Difficulty::Stepped(5).delay(0.2, 5) - 0.16).abs() < 1e-9);
+    assert!(Difficulty::Exponential.delay(0.2, 10) < 0.2);
+}
@@ -0,0 +1,316 @@
+use super::Game;
+use super::orientation::{Point, State, Direction, Grid};
+
+/// number of planes of the grid encodings: body, head, food, wall, portal, hazard
+const PLANES: usize = 6;
+
+/// how the board is presented to the agent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// one plane of `width * height` values per kind of tile: body, head, food, wall, portal, hazard
+    Planes,
+    /// the same planes for a window of `2 r + 1` tiles around the head,
+    /// rotated such that the snake heads up, on the hex grid the window is not rotated
+    Egocentric(u32),
+    /// danger straight ahead, left and right, the heading one-hot,
+    /// the way to the food relative to the board size and the length relative to the board area
+    Features,
+}
+
+impl Encoding {
+    /// `planes`, `egocentric` with a window of 11x11 tiles or `features`
+    pub fn from_name(name: &str) -> Option<Encoding> {
+        match name {
+            "planes" => Some(Encoding::Planes),
+            "egocentric" => Some(Encoding::Egocentric(5)),
+            "features" => Some(Encoding::Features),
+            _ => None,
+        }
+    }
+}
+
+/// what the agent gets for the outcome of a round
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Rewards {
+    pub food: f64,
+    pub death: f64,
+    /// added every round, negative values punish dawdling
+    pub step: f64,
+    pub win: f64,
+}
+
+impl Default for Rewards {
+    fn default() -> Rewards {
+        Rewards {
+            food: 1.,
+            death: -1.,
+            step: -0.01,
+            win: 10.,
+        }
+    }
+}
+
+/// action of the agent relative to the heading of the snake, such that it works on every grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Turn {
+    Straight,
+    Left,
+    Right,
+}
+
+impl Turn {
+    pub fn from_index(i: usize) -> Option<Turn> {
+        match i {
+            0 => Some(Turn::Straight),
+            1 => Some(Turn::Left),
+            2 => Some(Turn::Right),
+            _ => None,
+        }
+    }
+}
+
+/// details about the state of the episode, which are not part of the observation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Info {
+    pub score: i64,
+    pub length: usize,
+    pub round: u64,
+    pub won: bool,
+    /// the episode was cut short, because the snake did not eat for too long
+    pub truncated: bool,
+}
+
+/// reinforcement learning environment in the style of OpenAI gym
+///
+/// every `step` advances the game by exactly one round, independent of any clock,
+/// an autopilot of the game overrides the actions, which is useful as a baseline
+pub struct Env {
+    game: Game,
+    encoding: Encoding,
+    rewards: Rewards,
+    /// end the episode after this many rounds without food, against endless loops
+    max_idle: u64,
+    idle: u64,
+    /// the episode ended, until the next `reset`
+    done: bool,
+}
+
+impl Env {
+    pub fn new(game: Game, encoding: Encoding, rewards: Rewards) -> Env {
+        let (w, h) = game.size();
+        Env {
+            game,
+            encoding,
+            rewards,
+            max_idle: 2 * (w * h) as u64,
+            idle: 0,
+            done: false,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// start a new episode, with a seed it is reproducible
+    pub fn reset(&mut self, seed: Option<u64>) -> Vec<f32> {
+        if let Some(s) = seed {
+            self.game.seed(s);
+        }
+        self.game.restart();
+        self.idle = 0;
+        self.done = false;
+
+        self.observe()
+    }
+
+    /// returns the observation, the reward, whether the episode is done and some info,
+    /// after the end of the episode the last observation stays the same without any reward
+    pub fn step(&mut self, turn: Turn) -> (Vec<f32>, f64, bool, Info) {
+        if self.done {
+            let truncated = !self.game.finished();
+            return (self.observe(), 0., true, self.info(truncated))
+        }
+
+        let score = self.game.score;
+        self.game.steer(turn);
+        self.game.tick();
+        self.idle += 1;
+
+        let mut reward = self.rewards.step;
+        if self.game.score > score {
+            reward += self.rewards.food;
+            self.idle = 0;
+        }
+        if self.game.game_over {
+            reward += self.rewards.death;
+        }
+        if self.game.game_won {
+            reward += self.rewards.win;
+        }
+
+        let truncated = !self.game.finished() && self.idle >= self.max_idle;
+        self.done = self.game.finished() || truncated;
+
+        (self.observe(), reward, self.done, self.info(truncated))
+    }
+
+    pub fn observe(&self) -> Vec<f32> {
+        self.game.observe(self.encoding)
+    }
+
+    /// number of values of every observation
+    pub fn observation_len(&self) -> usize {
+        let (w, h) = self.game.size();
+        match self.encoding {
+            Encoding::Planes => PLANES * (w * h) as usize,
+            Encoding::Egocentric(r) => PLANES * ((2 * r + 1) * (2 * r + 1)) as usize,
+            Encoding::Features => 3 + self.game.grid().directions().len() + 3,
+        }
+    }
+
+    fn info(&self, truncated: bool) -> Info {
+        Info {
+            score: self.game.score,
+            length: self.game.snake.length,
+            round: self.game.round,
+            won: self.game.game_won,
+            truncated,
+        }
+    }
+}
+
+impl Game {
+    /// turn relative to the current heading, immediately
+    pub fn steer(&mut self, turn: Turn) {
+        let dir = match turn {
+            Turn::Straight => return,
+            Turn::Left => self.snake.left(),
+            Turn::Right => self.snake.right(),
+        };
+        self.snake.turn(dir);
+    }
+
+    /// index of the plane a tile belongs to, if any
+    fn plane(&self, p: &Point) -> Option<usize> {
+        if self.map.normalize(p) == *self.snake.head() {
+            return Some(1)
+        }
+        match self.map.at(p) {
+            State::Snake => Some(0),
+            State::Food => Some(2),
+            State::Wall => Some(3),
+            State::Portal => Some(4),
+            State::Hazard => Some(5),
+            State::Empty => None,
+        }
+    }
+
+    /// the board as seen by an agent
+    pub fn observe(&self, encoding: Encoding) -> Vec<f32> {
+        let (w, h) = (self.map.size.0 as usize, self.map.size.1 as usize);
+        match encoding {
+            Encoding::Planes => {
+                let mut obs = vec![0.; PLANES * w * h];
+                for y in 0..h {
+                    for x in 0..w {
+                        if let Some(c) = self.plane(&Point::new(x as i32, y as i32)) {
+                            obs[(c * h + y) * w + x] = 1.;
+                        }
+                    }
+                }
+                obs
+            }
+            Encoding::Egocentric(r) => {
+                let r = r as i32;
+                let n = 2 * r as usize + 1;
+                let head = *self.snake.head();
+                let mut obs = vec![0.; PLANES * n * n];
+                for j in 0..n {
+                    for i in 0..n {
+                        let (dx, dy) = (i as i32 - r, j as i32 - r);
+                        // rotate, such that up in the window is ahead of the snake
+                        let (dx, dy) = match (self.map.grid, self.snake.direction) {
+                            (Grid::Square, Direction::E) => (-dy, dx),
+                            (Grid::Square, Direction::S) => (-dx, -dy),
+                            (Grid::Square, Direction::W) => (dy, -dx),
+                            _ => (dx, dy),
+                        };
+                        let p = Point::new(head.x + dx, head.y + dy);
+                        if let Some(c) = self.plane(&p) {
+                            obs[(c * n + j) * n + i] = 1.;
+                        }
+                    }
+                }
+                obs
+            }
+            Encoding::Features => {
+                let head = self.snake.head();
+                let danger = |d: Direction| {
+                    match self.map.predict(&self.map.neighbor(head, d), 1) {
+                        State::Empty | State::Food => 0.,
+                        _ => 1.,
+                    }
+                };
+                let mut obs = vec![
+                    danger(self.snake.direction),
+                    danger(self.snake.left()),
+                    danger(self.snake.right()),
+                ];
+
+                for &d in self.map.grid.directions() {
+                    obs.push(if d == self.snake.direction { 1. } else { 0. });
+                }
+
+                // shortest way over the periodic boundaries
+                let wrap = |d: i32, n: usize| {
+                    let n = n as i32;
+                    let d = (d % n + n) % n;
+                    if d > n / 2 { d - n } else { d }
+                };
+                let food = self.map.get_food();
+                obs.push(wrap(food.x - head.x, w) as f32 / w as f32);
+                obs.push(wrap(food.y - head.y, h) as f32 / h as f32);
+                obs.push(self.snake.length as f32 / (w * h) as f32);
+
+                obs
+            }
+        }
+    }
+}
+
+#[test]
+fn test_reproducible() {
+    let run = || {
+        let mut env = Env::new(Game::new((10, 10)), Encoding::Features, Rewards::default());
+        let mut observations = vec![env.reset(Some(42))];
+        for i in 0..200 {
+            let (obs, _, done, _) = env.step(Turn::from_index(i % 7 % 3).unwrap());
+            assert_eq!(obs.len(), env.observation_len());
+            observations.push(obs);
+            if done {
+                observations.push(env.reset(None));
+            }
+        }
+        observations
+    };
+
+    assert_eq!(run(), run());
+}
+
+#[test]
+fn test_step_after_done() {
+    let mut env = Env::new(Game::new((10, 10)), Encoding::Planes, Rewards::default());
+    env.reset(Some(1));
+    let head = *env.game.snake.head();
+    env.game.map.add_wall(head + Direction::E);
+    let (last, _, done, info) = env.step(Turn::Straight);
+    assert!(done);
+
+    for &turn in &[Turn::Straight, Turn::Left, Turn::Right] {
+        assert_eq!(env.step(turn), (last.clone(), 0., true, info.clone()));
+    }
+    // the dead snake did not move on
+    assert_eq!(env.game.snake.head(), &head);
+}
@@ -0,0 +1,86 @@
+use std::sync::{Arc, Mutex};
+
+use super::{Game, Autopilot};
+use super::orientation::{Direction, State};
+
+/// what killed the snake
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cause {
+    Wall,
+    Snake,
+    Hazard,
+    /// the end of a circle of portals
+    Portal,
+    /// the time limit of the mode ran out
+    TimeUp,
+}
+
+impl Cause {
+    /// the snake ran into a tile of this state
+    pub fn from_state(state: State) -> Cause {
+        match state {
+            State::Wall => Cause::Wall,
+            State::Snake => Cause::Snake,
+            State::Hazard => Cause::Hazard,
+            State::Portal => Cause::Portal,
+            State::Empty | State::Food => unreachable!("the snake survives on {:?}", state),
+        }
+    }
+}
+
+/// something which happened in the game, for frontends, statistics or sounds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    FoodEaten { score: i64, length: usize },
+    Turned { from: Direction, to: Direction },
+    Died(Cause),
+    Won,
+    Restarted,
+    AutopilotChanged(Autopilot),
+    /// the new speed level, see `Game::speed_level`
+    SpeedChanged(i32),
+}
+
+/// shared by all copies of a game, but not by simulations
+pub type Observer = Arc<Mutex<dyn FnMut(&Event) + Send>>;
+
+impl Game {
+    /// call `f` for every event from now on
+    pub fn subscribe<F: FnMut(&Event) + Send + 'static>(&mut self, f: F) {
+        self.observers.push(Arc::new(Mutex::new(f)));
+    }
+
+    pub(super) fn emit(&self, event: Event) {
+        for observer in &self.observers {
+            (*observer.lock().unwrap())(&event);
+        }
+    }
+}
+
+#[test]
+fn test_events() {
+    use super::orientation::Point;
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let mut game = Game::new((10, 10));
+    let log = events.clone();
+    game.subscribe(move |e| log.lock().unwrap().push(*e));
+
+    // a wall two tiles ahead of the snake, which heads east, and the food out of the way
+    let head = *game.snake.head();
+    game.map.add_wall(Point::new(head.x + 2, head.y));
+    game.map.place_food(Point::new(head.x, (head.y + 2) % 10));
+    game.set_autopilot(Autopilot::None);
+    game.tick();
+    game.simulation().tick();
+    assert!(events.lock().unwrap().is_empty());
+    game.tick();
+    game.restart();
+    game.set_autopilot(Autopilot::Smart);
+
+    assert_eq!(*events.lock().unwrap(), vec![
+        Event::Died(Cause::Wall),
+        Event::Restarted,
+        Event::AutopilotChanged(Autopilot::Smart),
+    ]);
+}
@@ -0,0 +1,112 @@
+use super::orientation::Point;
+
+/// an obstacle which changes over time, its position only depends on the round
+#[derive(Debug, Clone, PartialEq)]
+pub enum Hazard {
+    /// a block moving one tile along the path every round, starting over at its end
+    Patrol(Vec<Point>),
+    /// a wall which is there for `period` rounds and gone for the next `period` rounds
+    Blinking(Point, u64),
+}
+
+impl Hazard {
+    /// a block moving back and forth between the waypoints,
+    /// first horizontally, then vertically
+    pub fn patrol(waypoints: &[Point]) -> Hazard {
+        let mut path = Vec::new();
+        for w in waypoints.windows(2) {
+            let (mut p, end) = (w[0], w[1]);
+            while p != end {
+                path.push(p);
+                if p.x != end.x {
+                    p.x += (end.x - p.x).signum();
+                } else {
+                    p.y += (end.y - p.y).signum();
+                }
+            }
+        }
+        if let Some(&last) = waypoints.last() {
+            path.push(last);
+        }
+
+        // and the same way back
+        let back: Vec<Point> = path.iter()
+                                   .rev()
+                                   .skip(1)
+                                   .take(path.len().saturating_sub(2))
+                                   .cloned()
+                                   .collect();
+        path.extend(back);
+
+        Hazard::Patrol(path)
+    }
+
+    /// where the hazard is in the given round, if it is there at all
+    pub fn position(&self, round: u64) -> Option<Point> {
+        match *self {
+            Hazard::Patrol(ref path) if path.is_empty() => None,
+            Hazard::Patrol(ref path) => Some(path[(round % path.len() as u64) as usize]),
+            Hazard::Blinking(p, period) => {
+                if (round / period.max(1)) % 2 == 0 {
+                    Some(p)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_patrol() {
+    let h = Hazard::patrol(&[Point::new(0, 0), Point::new(2, 1)]);
+    let path: Vec<Point> = (0..6).filter_map(|r| h.position(r)).collect();
+    assert_eq!(path, vec![
+        Point::new(0, 0), Point::new(1, 0), Point::new(2, 0), Point::new(2, 1),
+        Point::new(2, 0), Point::new(1, 0),
+    ]);
+    assert_eq!(h.position(6), Some(Point::new(0, 0)));
+
+    let b = Hazard::Blinking(Point::new(1, 1), 3);
+    assert_eq!(b.position(2), Some(Point::new(1, 1)));
+    assert_eq!(b.position(3), None);
+}
+
+#[test]
+fn test_run_into_snake() {
+    use std::sync::{Arc, Mutex};
+    use super::{Game, Autopilot, Event, Cause};
+    use super::orientation::Direction;
+
+    let play = |path: &dyn Fn(&Game) -> Vec<Point>| {
+        let mut game = Game::new((10, 10));
+        game.set_autopilot(Autopilot::None);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let log = events.clone();
+        // the food might be in the way
+        game.subscribe(move |e| if let Event::Died(_) = *e {
+            log.lock().unwrap().push(*e)
+        });
+
+        let path = path(&game);
+        game.map.add_hazard(Hazard::Patrol(path));
+        game.tick();
+        let events = events.lock().unwrap().clone();
+        events
+    };
+
+    // swapping places with the head, which heads east
+    let head_on = play(&|game| vec![*game.snake.head() + Direction::E, *game.snake.head()]);
+    assert_eq!(head_on, vec![Event::Died(Cause::Hazard)]);
+
+    // moving onto the body from the side
+    let body = play(&|game| {
+        let neck = game.snake.get_tail()[1];
+        vec![neck + Direction::N, neck]
+    });
+    assert_eq!(body, vec![Event::Died(Cause::Hazard)]);
+
+    // a hazard next to the snake does no harm
+    let beside = play(&|game| vec![*game.snake.head() + Direction::S, *game.snake.head() + Direction::S]);
+    assert!(beside.is_empty());
+}
@@ -0,0 +1,122 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use super::Game;
+
+/// color of the least visited tile
+const COLD: [f64; 3] = [34., 34., 34.];
+/// color of the most visited tile
+const HOT: [f64; 3] = [238., 51., 51.];
+
+/// how often the head of the snake visited every tile, over one or many games
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heatmap {
+    size: (u32, u32),
+    visits: Vec<u64>,
+}
+
+impl Heatmap {
+    pub fn new(size: (u32, u32)) -> Heatmap {
+        Heatmap {
+            size,
+            visits: vec![0; (size.0 * size.1) as usize],
+        }
+    }
+
+    /// count the tile of the head, call after every round
+    pub fn visit(&mut self, game: &Game) {
+        // levels of a campaign might be larger, they share the top left corner
+        let (w, h) = game.size();
+        if w > self.size.0 || h > self.size.1 {
+            self.grow((w.max(self.size.0), h.max(self.size.1)));
+        }
+
+        let head = game.snake.head();
+        self.visits[(head.y as u32 * self.size.0 + head.x as u32) as usize] += 1;
+    }
+
+    fn grow(&mut self, size: (u32, u32)) {
+        let mut visits = vec![0; (size.0 * size.1) as usize];
+        for (y, row) in self.visits.chunks(self.size.0 as usize).enumerate() {
+            let start = y * size.0 as usize;
+            visits[start..start + row.len()].copy_from_slice(row);
+        }
+        self.size = size;
+        self.visits = visits;
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> u64 {
+        self.visits[(y * self.size.0 + x) as usize]
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// one line per row of the board
+    pub fn save_csv<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        for row in self.visits.chunks(self.size.0 as usize) {
+            let row: Vec<String> = row.iter().map(|v| v.to_string()).collect();
+            writeln!(file, "{}", row.join(","))?;
+        }
+        Ok(())
+    }
+
+    /// rgba pixels with `scale` pixels per tile, the colors span from the least
+    /// to the most visited tile, such that small biases are still visible
+    pub fn pixels(&self, scale: u32) -> Vec<u8> {
+        let min = *self.visits.iter().min().unwrap_or(&0) as f64;
+        let max = *self.visits.iter().max().unwrap_or(&0) as f64;
+        let (w, h) = (self.size.0 * scale, self.size.1 * scale);
+
+        let mut pixels = Vec::with_capacity((4 * w * h) as usize);
+        for y in 0..h {
+            for x in 0..w {
+                let v = self.get(x / scale, y / scale);
+                let t = if max > min { (v as f64 - min) / (max - min) } else { 0. };
+                for i in 0..3 {
+                    pixels.push((COLD[i] + t * (HOT[i] - COLD[i])).round() as u8);
+                }
+                pixels.push(255);
+            }
+        }
+        pixels
+    }
+}
+
+#[test]
+fn test_visit() {
+    use super::Autopilot;
+    use super::map::Map;
+    use super::orientation::Point;
+
+    let mut game = Game::new((10, 10));
+    game.set_autopilot(Autopilot::None);
+    let mut heatmap = Heatmap::new(game.size());
+    let start = *game.snake.head();
+    heatmap.visit(&game);
+    game.tick();
+    heatmap.visit(&game);
+
+    // heading east
+    let next = *game.snake.head();
+    assert_eq!(next, game.map.normalize(&Point::new(start.x + 1, start.y)));
+    assert_eq!(heatmap.get(start.x as u32, start.y as u32), 1);
+    assert_eq!(heatmap.get(next.x as u32, next.y as u32), 1);
+    assert_eq!(heatmap.visits.iter().sum::<u64>(), 2);
+
+    let pixels = heatmap.pixels(2);
+    assert_eq!(pixels.len(), 4 * 20 * 20);
+    let pixel = |x: i32, y: i32| &pixels[4 * (2 * y * 20 + 2 * x) as usize..][..4];
+    assert_eq!(pixel(start.x, start.y), &[238, 51, 51, 255]);
+    assert_eq!(pixel(start.x, (start.y + 1) % 10), &[34, 34, 34, 255]);
+
+    // a larger level of a campaign
+    game.map = Map::new((12, 11));
+    heatmap.visit(&game);
+    assert_eq!(heatmap.size(), (12, 11));
+    assert_eq!(heatmap.get(start.x as u32, start.y as u32), 1);
+    assert_eq!(heatmap.get(next.x as u32, next.y as u32), 2);
+}
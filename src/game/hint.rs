@@ -0,0 +1,82 @@
+use super::{Game, Autopilot};
+use super::orientation::Point;
+
+/// advice for a human player, the autopilot does not steer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hint {
+    /// the tile the autopilot would move to next
+    pub next: Point,
+    /// the current heading leads into a dead end this many rounds ahead
+    pub dead_end: Option<u64>,
+}
+
+impl Game {
+    /// while playing manually, show where `autopilot` would go
+    /// and warn about dead ends up to `steps` rounds ahead
+    pub fn set_hints(&mut self, autopilot: Autopilot, steps: u64) {
+        self.hint_autopilot = autopilot;
+        self.hint_steps = steps;
+        self.update_hint();
+    }
+
+    pub fn hint(&self) -> Option<&Hint> {
+        self.hint.as_ref()
+    }
+
+    /// rounds until the snake, following the queued turns and going straight afterwards,
+    /// collides or enters a region too small to hold it
+    fn dead_end(&self) -> Option<u64> {
+        let mut sim = self.simulation();
+        for k in 1..=self.hint_steps {
+            // the state at the start of the next round
+            let mut probe = sim.simulation();
+            probe.snake.apply_queued_turn();
+            let direction = probe.snake.direction;
+            if probe.detect_hazard() || probe.space(direction) < probe.snake.length {
+                return Some(k)
+            }
+            sim.tick();
+            if sim.finished() {
+                break
+            }
+        }
+        None
+    }
+
+    /// ask the autopilot again, after every round and every turn of the player
+    pub fn update_hint(&mut self) {
+        if self.hint_autopilot == Autopilot::None || self.autopilot != Autopilot::None || self.finished() {
+            self.hint = None;
+            return
+        }
+
+        let mut advice = self.simulation();
+        advice.snake.apply_queued_turn();
+        advice.steer_with(self.hint_autopilot);
+
+        self.hint = Some(Hint {
+            next: self.map.neighbor(self.snake.head(), advice.snake.direction),
+            dead_end: self.dead_end(),
+        });
+    }
+}
+
+#[test]
+fn test_dead_end() {
+    let mut game = Game::new((10, 10));
+    game.set_hints(Autopilot::Smart, 3);
+    let hint = *game.hint().unwrap();
+    assert_eq!(hint.dead_end, None);
+    assert!(game.map.grid.directions()
+                         .iter()
+                         .any(|&d| game.map.neighbor(game.snake.head(), d) == hint.next));
+
+    // two tiles ahead of the snake, which heads east
+    let head = *game.snake.head();
+    game.map.add_wall(Point::new(head.x + 2, head.y));
+    game.update_hint();
+    assert_eq!(game.hint().unwrap().dead_end, Some(2));
+
+    game.set_autopilot(Autopilot::Smart);
+    assert_eq!(game.hint(), None);
+}
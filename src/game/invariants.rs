@@ -0,0 +1,179 @@
+//! random action sequences against the simulation, checking its invariants after every round
+
+use std::collections::HashSet;
+
+use piston::input::keyboard::Key;
+use rand::{Rng, SeedableRng, thread_rng};
+use rand::rngs::SmallRng;
+use quickcheck::{QuickCheck, StdGen, TestResult};
+
+use super::{Game, Autopilot, Mode, Campaign};
+use super::campaign::Level;
+use super::env::Turn;
+use super::orientation::{Grid, Point, State, best_first_search};
+
+/// everything which has to hold between two rounds, `full` tells whether the tail
+/// of this game already reached the length of the snake
+fn check(game: &Game, full: &mut bool) -> Result<(), String> {
+    let map = &game.map;
+    let tail: Vec<Point> = game.snake.get_tail().iter().cloned().collect();
+    let normalized = |p: &Point| map.normalize(p) == *p;
+
+    // the tail grows one tile per round until it reaches the length and keeps it from then on,
+    // only if the snake eats, while it grows in survival, the tail lags one round behind
+    let length = game.snake.length;
+    let lag = match game.mode {
+        Mode::Survival => 1,
+        _ => 0,
+    };
+    if tail.len() > length || *full && tail.len() + lag < length {
+        return Err(format!("tail of {} tiles, but length {}", tail.len(), length))
+    }
+    *full |= tail.len() == length;
+    if let Some(p) = tail.iter().find(|p| !normalized(p)) {
+        return Err(format!("snake at {:?} outside of the board", p))
+    }
+    if tail.iter().collect::<HashSet<_>>().len() != tail.len() {
+        return Err("snake overlaps itself".to_string())
+    }
+    if let Some(p) = map.get_walls().iter().find(|p| !normalized(p)) {
+        return Err(format!("wall at {:?} outside of the board", p))
+    }
+
+    let mut snake = 0;
+    let mut food = Vec::new();
+    for x in 0..map.size.0 as i32 {
+        for y in 0..map.size.1 as i32 {
+            let p = Point::new(x, y);
+            match map.at(&p) {
+                State::Snake => snake += 1,
+                State::Food => food.push(p),
+                _ => (),
+            }
+        }
+    }
+
+    if let Some(p) = tail.iter().find(|p| map.at(p) != State::Snake) {
+        return Err(format!("snake at {:?} is {:?} on the map", p, map.at(p)))
+    }
+    if snake != tail.len() {
+        return Err(format!("{} tiles of snake on the map, but a tail of {}", snake, tail.len()))
+    }
+
+    // the food vanishes, when the board is full
+    if !game.game_won && food != vec![*map.get_food()] {
+        return Err(format!("food at {:?} on the map, but at {:?} in the game", food, map.get_food()))
+    }
+
+    // panics if the search runs astray
+    if !game.finished() {
+        let next = game.snake.peek(map);
+        best_first_search(&next, game.snake.end(), map);
+        best_first_search(&next, map.get_food(), map);
+    }
+
+    Ok(())
+}
+
+/// steer or press a key, then play a round, a new game starts after the end of the last one
+fn act(game: &mut Game, action: u8) {
+    match action % 8 {
+        0 => game.steer(Turn::Straight),
+        1 => game.steer(Turn::Left),
+        2 => game.steer(Turn::Right),
+        3 => game.key_press(Key::Up),
+        4 => game.key_press(Key::Down),
+        5 => game.key_press(Key::Left),
+        6 => game.key_press(Key::Right),
+        _ => game.steer_with(Autopilot::Smart),
+    }
+    game.tick();
+
+    if game.finished() {
+        game.restart();
+    }
+}
+
+/// a level with random walls, a pair of portals, a patrol and a blinking wall,
+/// which leaves the start of the snake free
+fn random_level(rng: &mut SmallRng, (width, height): (u32, u32)) -> Level {
+    let (w, h) = (width as i32, height as i32);
+    let start = Point::new(rng.gen_range(0, w), rng.gen_range(0, h));
+    let mut rows = vec![vec!['.'; width as usize]; height as usize];
+    for row in rows.iter_mut() {
+        for tile in row.iter_mut() {
+            if rng.gen_range(0, 8) == 0 {
+                *tile = '#';
+            }
+        }
+    }
+    for i in 0..3 {
+        rows[start.y as usize][((start.x - i + w) % w) as usize] = '.';
+    }
+    rows[start.y as usize][start.x as usize] = 'S';
+
+    // the portals stay out of the row of the snake
+    let mut portal = || (rng.gen_range(0, width as usize), rng.gen_range(0, height as usize));
+    let (a, b) = (portal(), portal());
+    if a != b && a.1 != start.y as usize && b.1 != start.y as usize {
+        rows[a.1][a.0] = '1';
+        rows[b.1][b.0] = '1';
+    }
+
+    let mut point = || format!("{},{}", rng.gen_range(0, w), rng.gen_range(0, h));
+    let level = format!("goal = length {}\npatrol = {} {}\nblink = {} 5\n\n{}",
+                        4 + width * height / 4, point(), point(), point(),
+                        rows.iter().map(|r| r.iter().collect::<String>()).collect::<Vec<_>>().join("\n"));
+    Level::parse(&level).expect("the random level is invalid")
+}
+
+/// 0 is classic, 1 time attack, 2 survival, otherwise a campaign of random levels
+fn random_mode(rng: &mut SmallRng, rules: u8, size: (u32, u32)) -> Mode {
+    match rules % 4 {
+        0 => Mode::Classic,
+        1 => Mode::TimeAttack,
+        2 => Mode::Survival,
+        _ => Mode::Campaign(Campaign::new(vec![random_level(rng, size), random_level(rng, size)])),
+    }
+}
+
+fn random_games(seed: u64, size: (u8, u8), hex: bool, rules: u8, actions: Vec<u8>) -> TestResult {
+    // rows of the hex grid only wrap around with an even height
+    let width = 3 + size.0 as u32 % 14;
+    let mut height = 3 + size.1 as u32 % 12;
+    if hex {
+        height += height % 2;
+    }
+
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let mut game = Game::with_mode((width, height), random_mode(&mut rng, rules, (width, height)));
+    if hex {
+        game.set_grid(Grid::Hex);
+    }
+    game.seed(seed);
+    game.restart();
+
+    let mut full = false;
+    for (round, &action) in actions.iter().enumerate() {
+        act(&mut game, action);
+        // a new game or level starts with a new snake
+        if game.round == 0 {
+            full = false;
+        }
+        if let Err(e) = check(&game, &mut full) {
+            return TestResult::error(format!("after action {} of {:?}: {}", round, actions, e))
+        }
+    }
+    TestResult::passed()
+}
+
+#[test]
+fn test_invariants() {
+    // long action sequences, such that the snake grows
+    QuickCheck::new()
+        .gen(StdGen::new(thread_rng(), 500))
+        .tests(200)
+        .max_tests(1000)
+        .quickcheck(random_games as fn(u64, (u8, u8), bool, u8, Vec<u8>) -> TestResult);
+}
+
@@ -1,13 +1,24 @@
 use std::collections::HashMap;
-use rand::{thread_rng, Rng};
+use rand::{Rng, SeedableRng, FromEntropy};
+use rand::rngs::SmallRng;
 
-use super::orientation::{Point, State};
+use super::orientation::{Point, State, Direction, Grid, hex_distance};
+use super::hazard::Hazard;
 
+#[derive(Clone)]
 pub struct Map {
     pub size: (u32, u32),
+    pub grid: Grid,
     pub map: HashMap<Point, State>,
     walls: Vec<Point>,
+    /// every portal mapped to its partner
+    portals: HashMap<Point, Point>,
+    hazards: Vec<Hazard>,
+    /// the round the hazards are positioned for
+    round: u64,
     pub food: Point,
+    /// source of the food positions, seeded for reproducible games
+    rng: SmallRng,
 }
 
 impl Map {
@@ -16,9 +27,14 @@ impl Map {
 
         let mut m = Map {
             size,
+            grid: Grid::Square,
             map,
             walls: vec![],
+            portals: HashMap::new(),
+            hazards: Vec::new(),
+            round: 0,
             food: Point::new(0, 0),
+            rng: SmallRng::from_entropy(),
         };
 
         m.generate_food();
@@ -30,7 +46,26 @@ impl Map {
         *self.map.get(&periodic).unwrap_or(&State::Empty)
     }
 
-    pub fn manhattan(&self, p1: &Point, p2: &Point) -> i32 {
+    /// number of steps between two tiles, ignoring obstacles
+    pub fn distance(&self, p1: &Point, p2: &Point) -> i32 {
+        match self.grid {
+            Grid::Square => self.manhattan(p1, p2),
+            Grid::Hex => {
+                let (w, h) = (self.size.0 as i32, self.size.1 as i32);
+                // get shortest way over periodic boundaries
+                let mut best = i32::max_value();
+                for &i in &[-1, 0, 1] {
+                    for &j in &[-1, 0, 1] {
+                        let image = Point::new(p2.x + i * w, p2.y + j * h);
+                        best = best.min(hex_distance(p1, &image));
+                    }
+                }
+                best
+            }
+        }
+    }
+
+    fn manhattan(&self, p1: &Point, p2: &Point) -> i32 {
         let mut dx = p1.x - p2.x;
         let mut dy = p1.y - p2.y;
 
@@ -45,26 +80,33 @@ impl Map {
         dx.abs() + dy.abs()
     }
 
-    pub fn print(&self) {
-        for j in 0..self.size.1 {
-            for i in 0..self.size.0 {
+    /// one character per tile, row by row
+    pub fn rows(&self) -> Vec<Vec<char>> {
+        (0..self.size.1).map(|j| {
+            (0..self.size.0).map(|i| {
                 match self.at(&Point::new(i as i32, j as i32)) {
-                    State::Snake => print!("s"),
-                    State::Food => print!("f"),
-                    State::Empty => print!(" "),
-                    State::Wall => print!("#"),
+                    State::Snake => 'o',
+                    State::Food => '*',
+                    State::Empty => '.',
+                    State::Wall => '#',
+                    State::Portal => '%',
+                    State::Hazard => 'x',
                 }
-            }
-            println!("");
+            }).collect()
+        }).collect()
+    }
+
+    pub fn print(&self) {
+        for row in self.rows() {
+            println!("{}", row.iter().collect::<String>());
         }
     }
 
     pub fn generate_food(&mut self) -> Point {
-        let mut rng = thread_rng();
         let mut p;
         while {
-            let x = rng.gen_range::<i32>(0, self.size.0 as i32);
-            let y = rng.gen_range::<i32>(0, self.size.1 as i32);
+            let x = self.rng.gen_range::<i32>(0, self.size.0 as i32);
+            let y = self.rng.gen_range::<i32>(0, self.size.1 as i32);
             p = Point::new(x, y);
             self.at(&p) != State::Empty
         } {}
@@ -73,18 +115,70 @@ impl Map {
         self.food
     }
 
+    /// draw the food from another random number generator, e.g., a seeded one,
+    /// the current food is placed anew
+    pub fn set_rng(&mut self, rng: SmallRng) {
+        self.rng = rng;
+        if self.at(&self.food) == State::Food {
+            let old = self.food;
+            self.free(old);
+        }
+        self.generate_food();
+    }
+
+    pub fn rng(&mut self) -> &mut SmallRng {
+        &mut self.rng
+    }
+
+    /// a new random number generator seeded by this one, which is not advanced,
+    /// such that the food of a copy of the map does not follow the original
+    pub fn fork_rng(&self) -> SmallRng {
+        SmallRng::from_rng(self.rng.clone()).expect("Could not seed the random number generator")
+    }
+
+    /// continue with another random number generator, the food stays where it is
+    pub fn replace_rng(&mut self, rng: SmallRng) {
+        self.rng = rng;
+    }
+
     pub fn consumed_food(&mut self) {
         self.generate_food();
     }
 
+    /// put the food at a given position instead of a random one
+    pub fn place_food(&mut self, p: Point) {
+        if self.at(&self.food) == State::Food {
+            let old = self.food;
+            self.free(old);
+        }
+        self.food = p;
+        self.map.insert(p, State::Food);
+    }
+
     pub fn get_walls(&self) -> &Vec<Point> {
         &self.walls
     }
 
+    pub fn get_portals(&self) -> &HashMap<Point, Point> {
+        &self.portals
+    }
+
     pub fn get_food(&self) -> &Point {
         &self.food
     }
 
+    /// number of tiles, which the snake or the food may occupy, i.e., without walls,
+    /// portals and the tiles the hazards cover right now
+    pub fn open_tiles(&self) -> u32 {
+        let (w, h) = (self.size.0 as i32, self.size.1 as i32);
+        (0..w).flat_map(|x| (0..h).map(move |y| Point::new(x, y)))
+              .filter(|p| match self.at(p) {
+                  State::Empty | State::Food | State::Snake => true,
+                  State::Wall | State::Portal | State::Hazard => false,
+              })
+              .count() as u32
+    }
+
     pub fn free(&mut self, p: Point) {
         let p = self.normalize(&p);
         self.map.remove(&p);
@@ -103,6 +197,109 @@ impl Map {
         }
     }
 
+    pub fn add_wall(&mut self, p: Point) {
+        let p = self.normalize(&p);
+        let food = self.at(&p) == State::Food;
+
+        self.walls.push(p);
+        self.map.insert(p, State::Wall);
+
+        // the food was buried, put it somewhere else
+        if food {
+            self.generate_food();
+        }
+    }
+
+    /// connect two tiles, the snake entering one leaves the other in the same direction
+    pub fn add_portals(&mut self, a: Point, b: Point) {
+        let a = self.normalize(&a);
+        let b = self.normalize(&b);
+        let food = self.at(&a) == State::Food || self.at(&b) == State::Food;
+
+        self.portals.insert(a, b);
+        self.portals.insert(b, a);
+        self.map.insert(a, State::Portal);
+        self.map.insert(b, State::Portal);
+
+        if food {
+            self.generate_food();
+        }
+    }
+
+    pub fn add_hazard(&mut self, hazard: Hazard) {
+        self.hazards.push(hazard);
+        let round = self.round;
+        self.move_hazards(round);
+    }
+
+    /// tiles currently occupied by hazards
+    pub fn get_hazards(&self) -> Vec<Point> {
+        self.hazards.iter()
+                    .filter_map(|h| h.position(self.round))
+                    .map(|p| self.normalize(&p))
+                    .collect()
+    }
+
+    /// move the hazards to their positions in `round`, food which is run over moves
+    /// somewhere else, returns true if one of them ran into the snake
+    pub fn move_hazards(&mut self, round: u64) -> bool {
+        for p in self.get_hazards() {
+            if self.at(&p) == State::Hazard {
+                self.map.remove(&p);
+            }
+        }
+        self.round = round;
+
+        let mut food = false;
+        let mut hit = false;
+        for p in self.get_hazards() {
+            match self.at(&p) {
+                State::Wall | State::Portal => continue,
+                State::Snake => {
+                    hit = true;
+                    continue
+                }
+                State::Food => food = true,
+                State::Empty | State::Hazard => (),
+            }
+            self.map.insert(p, State::Hazard);
+        }
+
+        // the food was run over, put it somewhere else
+        if food {
+            self.generate_food();
+        }
+        hit
+    }
+
+    /// the state of a tile `ahead` rounds in the future, as far as the hazards are concerned
+    pub fn predict(&self, p: &Point, ahead: u64) -> State {
+        let p = self.normalize(p);
+        let hazard = self.hazards.iter()
+                                 .filter_map(|h| h.position(self.round + ahead))
+                                 .any(|h| self.normalize(&h) == p);
+        let state = self.at(&p);
+        match state {
+            State::Wall | State::Portal => state,
+            _ if hazard => State::Hazard,
+            State::Hazard => State::Empty,
+            _ => state,
+        }
+    }
+
+    /// the tile reached by moving from `p` in direction `dir`, passing through portals
+    pub fn neighbor(&self, p: &Point, dir: Direction) -> Point {
+        let mut next = self.normalize(&(*p + dir));
+        // follow chained portals, but do not loop forever
+        for _ in 0..self.portals.len() {
+            match self.portals.get(&next) {
+                Some(exit) => next = self.normalize(&(*exit + dir)),
+                None => break,
+            }
+        }
+        next
+    }
+
     pub fn occupy(&mut self, p: Point) {
         let p = self.normalize(&p);
         self.map.insert(p, State::Snake);
@@ -137,3 +334,14 @@ fn test_normalize() {
     assert_eq!(map.normalize(&Point::new(-1, 3)), Point::new(2, 0));
     assert_eq!(map.normalize(&Point::new(3, -1)), Point::new(0, 2));
 }
+
+#[test]
+fn test_portal() {
+    let mut map = Map::new((5, 5));
+    map.add_portals(Point::new(1, 1), Point::new(3, 4));
+    assert_eq!(map.at(&Point::new(3, 4)), State::Portal);
+    assert_eq!(map.neighbor(&Point::new(0, 1), Direction::E), Point::new(4, 4));
+    // leaving through the periodic boundary
+    assert_eq!(map.neighbor(&Point::new(3, 3), Direction::S), Point::new(1, 2));
+    assert_eq!(map.neighbor(&Point::new(0, 0), Direction::W), Point::new(4, 0));
+}
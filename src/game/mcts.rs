@@ -0,0 +1,229 @@
+use std::time::Instant;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+
+use super::{Game, Autopilot, Mode, Replay};
+use super::env::Turn;
+
+const TURNS: [Turn; 3] = [Turn::Straight, Turn::Left, Turn::Right];
+/// rounds played by the heuristic after leaving the tree
+const ROLLOUT: u32 = 30;
+/// discount of later rewards, food now is better than food later
+const GAMMA: f64 = 0.95;
+const FOOD: f64 = 1.;
+const DEATH: f64 = -5.;
+/// weight of the exploration term of UCT
+const EXPLORATION: f64 = 0.5;
+/// fraction of random moves in the rollouts
+const EPSILON: f64 = 0.2;
+
+/// statistics of all games in the tree which passed a state
+struct Node {
+    children: [Option<usize>; 3],
+    visits: f64,
+    value: f64,
+}
+
+impl Node {
+    fn new() -> Node {
+        Node {
+            children: [None; 3],
+            visits: 0.,
+            value: 0.,
+        }
+    }
+}
+
+impl Game {
+    /// a copy of the game, which can be played without side effects
+    /// and without knowing where the food will appear
+    pub fn simulation(&self) -> Game {
+        let mut sim = self.clone();
+        sim.map.replace_rng(self.map.fork_rng());
+        sim.recording = Replay::new(self.map.size);
+        sim.observers.clear();
+        sim.autopilot = Autopilot::None;
+        sim.hint_autopilot = Autopilot::None;
+        sim.hint = None;
+        sim.trace = None;
+        sim.decision = None;
+        sim.playback = None;
+        if let Mode::Campaign(ref mut c) = sim.mode {
+            c.forget_progress();
+        }
+        sim
+    }
+
+    /// advance the simulation by one round and return the reward
+    fn simulate(&mut self, turn: Option<Turn>) -> f64 {
+        let score = self.score;
+        match turn {
+            Some(t) => self.steer(t),
+            None => self.rollout_policy(),
+        }
+        self.tick();
+
+        if self.game_over {
+            DEATH
+        } else {
+            FOOD * (self.score - score) as f64
+        }
+    }
+
+    /// mostly the stupid autopilot, sometimes a random turn, unless it is deadly
+    fn rollout_policy(&mut self) {
+        if self.map.rng().gen::<f64>() < EPSILON {
+            let original = self.snake.direction;
+            let turn = TURNS[self.map.rng().gen_range(0, 3)];
+            self.steer(turn);
+            if !self.detect_hazard() {
+                return
+            }
+            self.snake.turn(original);
+        }
+        self.stupid_autopilot();
+    }
+
+    /// the child to descend into, unexplored children first, then by UCT
+    fn select(nodes: &[Node], node: usize) -> usize {
+        if let Some(i) = nodes[node].children.iter().position(|c| c.is_none()) {
+            return i
+        }
+
+        let ln = nodes[node].visits.ln();
+        let uct = |i: usize| {
+            let child = &nodes[nodes[node].children[i].unwrap()];
+            child.value / child.visits + EXPLORATION * (ln / child.visits).sqrt()
+        };
+        (0..3).fold(0, |best, i| if uct(i) > uct(best) { i } else { best })
+    }
+
+    /// the turn with the best simulations on average within the time budget
+    /// or the number of iterations
+    fn mcts(&self) -> Turn {
+        let start = Instant::now();
+        let done = |iteration: u64| match self.mcts_iterations {
+            Some(n) => iteration >= n,
+            None => start.elapsed() >= self.mcts_budget,
+        };
+        let mut root = self.simulation();
+        let mut nodes = vec![Node::new()];
+
+        let mut iteration = 0;
+        while !done(iteration) {
+            iteration += 1;
+            // every simulation sees other food and other random moves
+            let mut sim = root.clone();
+            let rng = SmallRng::from_rng(root.map.rng()).expect("Could not seed the random number generator");
+            sim.map.replace_rng(rng);
+            let mut path = vec![0];
+            let mut ret = 0.;
+            let mut discount = 1.;
+
+            // descend through the tree and expand one new node
+            while !sim.finished() {
+                let node = *path.last().unwrap();
+                let i = Game::select(&nodes, node);
+                ret += discount * sim.simulate(Some(TURNS[i]));
+                discount *= GAMMA;
+
+                match nodes[node].children[i] {
+                    Some(child) => path.push(child),
+                    None => {
+                        nodes.push(Node::new());
+                        let child = nodes.len() - 1;
+                        nodes[node].children[i] = Some(child);
+                        path.push(child);
+                        break
+                    }
+                }
+            }
+
+            for _ in 0..ROLLOUT {
+                if sim.finished() {
+                    break
+                }
+                ret += discount * sim.simulate(None);
+                discount *= GAMMA;
+            }
+
+            // food beyond the horizon of the rollout is still worth something
+            if !sim.finished() {
+                let (w, h) = sim.map.size;
+                let d = sim.map.distance(sim.snake.head(), sim.map.get_food()) as f64;
+                ret += discount * FOOD * (1. - d / (w + h) as f64);
+            }
+
+            for &n in &path {
+                nodes[n].visits += 1.;
+                nodes[n].value += ret;
+            }
+        }
+
+        // the values differ only slightly, the most visited turn might just be the lucky one
+        let mean = |i: usize| nodes[0].children[i].map_or(DEATH, |c| nodes[c].value / nodes[c].visits);
+        TURNS[(0..3).fold(0, |best, i| if mean(i) > mean(best) { i } else { best })]
+    }
+
+    pub fn mcts_autopilot(&mut self) {
+        let turn = self.mcts();
+        self.steer(turn);
+    }
+}
+
+#[test]
+fn test_simulation() {
+    let mut game = Game::new((10, 10));
+    game.seed(1);
+    let mut sim = game.simulation();
+    for _ in 0..10 {
+        sim.tick();
+    }
+    assert_eq!(game.round(), 0);
+    assert_eq!(sim.round(), 10);
+
+    // without side effects the same moves lead to the same game
+    let mut again = game.simulation();
+    for _ in 0..10 {
+        again.tick();
+    }
+    assert_eq!(again.to_text(), sim.to_text());
+
+    // the simulation neither knows the future food nor copies the recording
+    game.tick();
+    let mut sim = game.simulation();
+    assert!(sim.replay().directions.is_empty());
+    let next: Vec<u64> = (0..3).map(|_| game.map.rng().gen()).collect();
+    let guess: Vec<u64> = (0..3).map(|_| sim.map.rng().gen()).collect();
+    assert!(next != guess);
+}
+
+#[test]
+fn test_avoid_wall() {
+    use super::Campaign;
+    use super::campaign::Level;
+
+    // the wall is straight ahead of the snake
+    let level = Level::parse("goal = length 20\n\n........\n..S#....\n........\n........").unwrap();
+    let mut game = Game::with_mode((8, 4), Mode::Campaign(Campaign::new(vec![level])));
+    game.seed(1);
+    game.restart();
+    game.set_mcts_iterations(Some(100));
+
+    let mut straight = game.clone();
+    straight.tick();
+    assert!(straight.finished());
+
+    game.set_autopilot(Autopilot::Mcts);
+    game.tick();
+    assert!(!game.finished());
+
+    // with a number of iterations instead of a time budget the search is reproducible
+    let mut again = game.clone();
+    for _ in 0..20 {
+        game.tick();
+        again.tick();
+    }
+    assert_eq!(again.to_text(), game.to_text());
+}
@@ -2,29 +2,109 @@ mod orientation;
 mod map;
 mod snake;
 mod autopilot;
+mod replay;
+mod bindings;
+mod clock;
+mod difficulty;
+mod campaign;
+mod mode;
+mod hazard;
+mod neural;
+mod qlearning;
+mod mcts;
+mod hint;
+mod trace;
+mod events;
+mod stats;
+mod heatmap;
+#[cfg(test)]
+mod invariants;
 
+#[cfg(feature = "frontends")]
 pub mod renderable;
+#[cfg(feature = "frontends")]
+pub mod terminal;
+pub mod env;
+
+pub use self::autopilot::Autopilot;
+pub use self::replay::{Replay, Rules};
+pub use self::bindings::{Bindings, parse_key};
+pub use self::difficulty::Difficulty;
+pub use self::campaign::{Campaign, Level};
+pub use self::mode::Mode;
+pub use self::neural::Network;
+pub use self::qlearning::QTable;
+pub use self::hint::Hint;
+pub use self::trace::{Trace, Decision, Candidate};
+pub use self::events::{Event, Cause, Observer};
+pub use self::stats::Stats;
+pub use self::heatmap::Heatmap;
+
+use std::time::Duration;
 
 use piston::input::keyboard::Key;
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
 
 use self::snake::Snake;
 use self::map::Map;
 use self::orientation::{Direction, State, Point};
-use self::autopilot::Autopilot;
+pub use self::orientation::Grid;
+use self::bindings::Action;
+use self::clock::Clock;
 
+/// delay between two rounds in seconds at the start of the game
+const DEFAULT_DELAY: f64 = 0.2;
+/// factor by which the delay changes on speed up
+const SPEED_UP: f64 = 0.8;
+
+#[derive(Clone)]
 pub struct Game {
     snake: Snake,
     map: Map,
-    delay: f64,
+    clock: Clock,
+    /// delay at score 0, which is shortened depending on the difficulty
+    base_delay: f64,
+    difficulty: Difficulty,
+    /// time since the game ended
     time: f64,
     round: u64,
+    /// the state of the game changed since it was last rendered,
+    /// frontends which do not interpolate only need to redraw if this is set
     pub dirty: bool,
     pub score: i64,
     autopilot: Autopilot,
     game_over: bool,
     game_won: bool,
     paused: bool,
-    help_texts: Vec<&'static str>,
+    queue_depth: usize,
+    bindings: Bindings,
+    help_texts: Vec<String>,
+    recording: Replay,
+    playback: Option<Replay>,
+    mode: Mode,
+    /// game time since the start, or the start of the level in the campaign
+    elapsed: f64,
+    /// brain of the neural autopilot
+    network: Option<Network>,
+    /// values of the q-learning autopilot
+    q_table: Option<QTable>,
+    /// thinking time of the tree search autopilot per round
+    mcts_budget: Duration,
+    /// number of simulations of the tree search per round, which replaces the thinking time
+    mcts_iterations: Option<u64>,
+    /// the autopilot giving hints to the player
+    hint_autopilot: Autopilot,
+    /// how many rounds ahead the player is warned about dead ends
+    hint_steps: u64,
+    hint: Option<Hint>,
+    /// log of the decisions of the autopilot
+    trace: Option<Trace>,
+    /// the decision of the current round, only while tracing
+    decision: Option<Decision>,
+    /// notified about every event
+    observers: Vec<Observer>,
+    stats: Stats,
 }
 
 impl Game {
@@ -33,10 +113,12 @@ impl Game {
         let snake = Snake::new(size);
         map.init_snake(snake.get_tail().iter());
 
-        Game {
+        let mut game = Game {
             snake,
             map,
-            delay: 0.2,
+            clock: Clock::new(DEFAULT_DELAY),
+            base_delay: DEFAULT_DELAY,
+            difficulty: Difficulty::Constant,
             time: 0.,
             round: 0,
             dirty: true,
@@ -45,23 +127,53 @@ impl Game {
             game_over: false,
             game_won: false,
             paused: false,
-            help_texts: vec![
-                "R: Restart",
-                "E: Speed up",
-                "Q: Slow down",
-                "WASD: Steer",
-                "F: Stupid Autopilot",
-                "T: Smart Autopilot",
-                "G: Boring Autopilot",
-                "M: Manual Control",
-                "P: Pause and Help",
-                "Esc: Exit"
-            ],
-        }
+            queue_depth: 3,
+            help_texts: Bindings::default().help_texts(),
+            bindings: Bindings::default(),
+            recording: Replay::new(size),
+            playback: None,
+            mode: Mode::Classic,
+            elapsed: 0.,
+            network: None,
+            q_table: None,
+            mcts_budget: Duration::from_millis(20),
+            mcts_iterations: None,
+            hint_autopilot: Autopilot::None,
+            hint_steps: 0,
+            hint: None,
+            trace: None,
+            decision: None,
+            observers: Vec::new(),
+            stats: Stats::default(),
+        };
+
+        game.start_recording();
+        game
+    }
+
+    /// a game with other rules than the classic mode
+    pub fn with_mode(size: (u32, u32), mode: Mode) -> Game {
+        let size = match mode {
+            Mode::Campaign(ref c) => c.level().size,
+            _ => size,
+        };
+        let mut game = Game::new(size);
+        game.mode = mode;
+        game.restart();
+        game
+    }
+
+    /// a game which replays the recorded moves, `mode` should follow `replay.rules`
+    pub fn from_replay(replay: Replay, mode: Mode) -> Game {
+        let mut game = Game::with_mode(replay.size, mode);
+        game.map.grid = replay.grid;
+        game.playback = Some(replay);
+        game.restart();
+        game
     }
 
     fn peek(&mut self) -> State {
-        let p = self.snake.peek();
+        let p = self.snake.peek(&self.map);
         self.map.at(&p)
     }
 
@@ -89,44 +201,103 @@ impl Game {
             return
         }
 
-        self.time += dt;
+        for _ in 0..self.clock.advance(dt) {
+            self.tick();
+            if self.game_over {
+                break
+            }
+        }
 
-        if self.round as f64 * self.delay > self.time {
+        // the dead snake should not move any further
+        self.snake.progress = if self.game_over { 1. } else { self.clock.alpha() };
+    }
+
+    /// advance the game by exactly one round, independent of the elapsed time,
+    /// a finished game stays as it is
+    pub fn tick(&mut self) {
+        if self.finished() {
             return
         }
         self.dirty = true;
+        self.record_delay();
+
+        if !self.mode_tick() {
+            return
+        }
+
+        let heading = self.snake.direction;
+        self.snake.apply_queued_turn();
 
-        match self.autopilot {
-            Autopilot::Stupid => self.stupid_autopilot(),
-            Autopilot::Smart => self.smart_autopilot(),
-            Autopilot::Boring => self.boring_autopilot(),
-            _ => (),
+        let autopilot = self.autopilot;
+        self.begin_decision();
+        self.steer_with(autopilot);
+
+        if let Some(ref replay) = self.playback {
+            self.snake.turn(replay.directions[self.round as usize]);
+        }
+        self.recording.directions.push(self.snake.direction);
+        let turned = self.snake.direction != heading;
+        if turned {
+            self.emit(Event::Turned { from: heading, to: self.snake.direction });
         }
 
         self.round += 1;
-        match self.peek() {
-            State::Snake | State::Wall => self.game_over(),
+        // the hazards move first, the snake collides with their new positions,
+        // which also covers a hazard running into the snake, e.g., head-on
+        let food = self.map.food;
+        let hit = self.map.move_hazards(self.round);
+        if self.map.food != food {
+            self.relocated_food();
+        }
+        let next = if hit { State::Hazard } else { self.peek() };
+        match next {
+            // a portal is only reached at the end of a circle of portals
+            State::Snake | State::Wall | State::Portal | State::Hazard => self.game_over(Cause::from_state(next)),
             State::Food => {
                 self.snake.feed();
                 // test if we filled the whole map
-                if self.snake.length as u32 >= self.map.size.1 * self.map.size.0 - 1 {
+                let free = self.map.open_tiles();
+                if self.snake.length as u32 >= free - 1 {
                     self.game_won();
                     // remove food from sight
                     self.map.food = Point::new(-1, -1);
                 } else {
-                    self.map.consumed_food();
+                    self.next_food();
                 }
                 self.snake.step(&mut self.map);
                 self.score += 1;
+                self.emit(Event::FoodEaten { score: self.score, length: self.snake.length });
+                self.adjust_speed();
+                self.mode_fed();
             }
             State::Empty => self.snake.step(&mut self.map),
         }
+
+        self.record_round(turned, next == State::Food);
+        self.end_decision(match next {
+            State::Empty => "moved",
+            State::Food if self.game_won => "won",
+            State::Food => "ate",
+            _ => "died",
+        });
+        if self.replay_ended() {
+            self.end_replay();
+        }
+        self.update_hint();
     }
 
 
-    pub fn key_press(&mut self, key: Key) {
-        use piston::input::keyboard::Key::*;
+    /// how many turns are buffered, if they are pressed faster than the snake moves
+    pub fn set_queue_depth(&mut self, depth: usize) {
+        self.queue_depth = depth;
+    }
+
+    pub fn set_bindings(&mut self, bindings: Bindings) {
+        self.help_texts = bindings.help_texts();
+        self.bindings = bindings;
+    }
 
+    pub fn key_press(&mut self, key: Key) {
         enum Command {
             Turn(Direction),
             ChangeSpeed(f64),
@@ -136,29 +307,43 @@ impl Game {
             None
         }
 
-        let cmd = match key {
-            Up | W => Command::Turn(Direction::N),
-            Down | S => Command::Turn(Direction::S),
-            Right | D => Command::Turn(Direction::E),
-            Left | A => Command::Turn(Direction::W),
-            E => Command::ChangeSpeed(0.8),
-            Q => Command::ChangeSpeed(1./0.8),
-            F => Command::Autopilot(Autopilot::Stupid),
-            T => Command::Autopilot(Autopilot::Smart),
-            G => Command::Autopilot(Autopilot::Boring),
-            M => Command::Autopilot(Autopilot::None),
-            H | P => Command::Help,
-            R => Command::Restart,
-            _ => Command::None
+        let cmd = match self.bindings.action(key) {
+            Some(Action::Up) => Command::Turn(Direction::N),
+            Some(Action::Down) => Command::Turn(Direction::S),
+            Some(Action::Right) => Command::Turn(Direction::E),
+            Some(Action::Left) => Command::Turn(Direction::W),
+            Some(Action::SpeedUp) => Command::ChangeSpeed(SPEED_UP),
+            Some(Action::SlowDown) => Command::ChangeSpeed(1./SPEED_UP),
+            Some(Action::StupidAutopilot) => Command::Autopilot(Autopilot::Stupid),
+            Some(Action::SmartAutopilot) => Command::Autopilot(Autopilot::Smart),
+            Some(Action::BoringAutopilot) => Command::Autopilot(Autopilot::Boring),
+            Some(Action::Manual) => Command::Autopilot(Autopilot::None),
+            Some(Action::Pause) => Command::Help,
+            Some(Action::Restart) => Command::Restart,
+            None => Command::None
         };
 
         match cmd {
-            Command::Turn(t) if !self.snake.reverse(t) => self.snake.turn(t),
+            Command::Turn(t) => {
+                let t = match self.map.grid {
+                    Grid::Square => Some(t),
+                    // there is no north and south, left and right turn by 60 degrees
+                    Grid::Hex => match t {
+                        Direction::W => Some(Grid::Hex.left(self.snake.queued_direction())),
+                        Direction::E => Some(Grid::Hex.right(self.snake.queued_direction())),
+                        _ => None,
+                    },
+                };
+                if let Some(t) = t {
+                    self.snake.queue_turn(t, self.queue_depth);
+                    self.update_hint();
+                }
+            }
             Command::ChangeSpeed(f) => {
-                self.delay *= f;
-                self.time = self.round as f64 * self.delay;
+                self.base_delay *= f;
+                self.adjust_speed();
             },
-            Command::Autopilot(a) => self.autopilot = a,
+            Command::Autopilot(a) => self.set_autopilot(a),
             Command::Help => {
                 if self.paused {
                     self.resume()
@@ -170,31 +355,225 @@ impl Game {
             _ => self.resume(),
         }
 
+        self.dirty = true;
+    }
+
+    /// the recorded position of the next food during a replay
+    fn recorded_food(&self) -> Option<Point> {
+        self.playback.as_ref()
+                     .and_then(|r| r.food.get(self.recording.food.len()).cloned())
+    }
+
+    fn next_food(&mut self) {
+        match self.recorded_food() {
+            Some(p) => self.map.place_food(p),
+            None => self.map.consumed_food(),
+        }
+        self.recording.food.push(self.map.food);
+    }
+
+    /// the map moved the food, e.g., because a wall buried it
+    fn relocated_food(&mut self) {
+        if let Some(p) = self.recorded_food() {
+            self.map.place_food(p);
+        }
+        self.recording.food.push(self.map.food);
+    }
+
+    /// the speed of this round, as it was recorded or into the recording
+    fn record_delay(&mut self) {
+        self.recorded_delay();
+        let delay = self.delay();
+        if self.recording.delays.last().map(|&(_, d)| d) != Some(delay) {
+            self.recording.delays.push((self.round, delay));
+        }
+    }
+
+    /// the speed of a replay, which overrides the difficulty and the keys
+    fn recorded_delay(&mut self) {
+        let round = self.round;
+        if let Some(delay) = self.playback.as_ref().and_then(|r| r.delay(round)) {
+            self.clock.set_delay(delay);
+        }
+    }
+
+    fn start_recording(&mut self) {
+        if let Some(ref replay) = self.playback {
+            self.map.place_food(replay.food[0]);
+        }
+        self.recording = Replay::new(self.map.size);
+        self.recording.grid = self.map.grid;
+        self.recording.rules = self.mode.rules();
+        self.recording.food.push(self.map.food);
+    }
+
+    /// the recording of the current game
+    pub fn replay(&self) -> &Replay {
+        &self.recording
+    }
+
+    /// make the food positions and the decisions of the autopilots reproducible
+    pub fn seed(&mut self, seed: u64) {
+        self.map.set_rng(SmallRng::seed_from_u64(seed));
+        // the food moved, a game which just started records it as the first
+        if self.round == 0 {
+            self.start_recording();
+        } else {
+            self.relocated_food();
+        }
+    }
+
+    /// play on another kind of tiles, starts a new game
+    pub fn set_grid(&mut self, grid: Grid) {
+        self.map.grid = grid;
+        self.restart();
     }
 
-    fn game_over(&mut self) {
-        println!("Game Over!");
+    pub fn grid(&self) -> Grid {
+        self.map.grid
+    }
+
+    pub fn set_autopilot(&mut self, autopilot: Autopilot) {
+        if autopilot != self.autopilot {
+            self.autopilot = autopilot;
+            self.emit(Event::AutopilotChanged(autopilot));
+        }
+        self.update_hint();
+    }
+
+    /// the network steering the neural autopilot
+    pub fn set_network(&mut self, network: Network) {
+        self.network = Some(network);
+    }
+
+    /// the table of the q-learning autopilot
+    pub fn set_q_table(&mut self, table: QTable) {
+        self.q_table = Some(table);
+    }
+
+    pub fn set_mcts_budget(&mut self, budget: Duration) {
+        self.mcts_budget = budget;
+    }
+
+    /// a fixed number of simulations instead of the thinking time,
+    /// such that the tree search does not depend on the speed of the machine
+    pub fn set_mcts_iterations(&mut self, iterations: Option<u64>) {
+        self.mcts_iterations = iterations;
+    }
+
+    pub fn round(&self) -> u64 {
+        self.round
+    }
+
+    pub fn delay(&self) -> f64 {
+        self.clock.delay()
+    }
+
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+        self.adjust_speed();
+    }
+
+    fn adjust_speed(&mut self) {
+        let level = self.speed_level();
+        let delay = self.difficulty.delay(self.base_delay, self.score);
+        self.clock.set_delay(delay);
+        if self.speed_level() != level {
+            self.emit(Event::SpeedChanged(self.speed_level()));
+        }
+    }
+
+    /// the speed as number of speed ups from the initial speed, starting at 1
+    pub fn speed_level(&self) -> i32 {
+        1 + ((DEFAULT_DELAY / self.delay()).ln() / (1. / SPEED_UP).ln()).round() as i32
+    }
+
+    fn replay_ended(&self) -> bool {
+        match self.playback {
+            Some(ref r) => self.round as usize >= r.directions.len(),
+            None => false,
+        }
+    }
+
+    /// true if the game is over, won or the replay ran out of moves
+    pub fn finished(&self) -> bool {
+        self.game_over || self.game_won || self.replay_ended()
+    }
+
+    /// true if the snake filled the board, or the campaign was completed
+    pub fn won(&self) -> bool {
+        self.game_won
+    }
+
+    fn game_over(&mut self, cause: Cause) {
         self.game_over = true;
         self.time = 0.;
+        self.emit(Event::Died(cause));
     }
 
     fn game_won(&mut self) {
-        println!("Game Won!");
         self.game_won = true;
+        self.emit(Event::Won);
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.map.size
     }
 
-    fn restart(&mut self) {
-        self.map = Map::new(self.map.size);
-        self.snake = Snake::new(self.map.size);
-        self.map.init_snake(self.snake.get_tail().iter());
+    pub fn restart(&mut self) {
+        let grid = self.map.grid;
+        // continue the sequence of random numbers, such that seeded games stay reproducible
+        let rng = self.map.rng().clone();
+        match self.mode {
+            Mode::Campaign(ref c) => {
+                let (map, snake) = c.level().board();
+                self.map = map;
+                self.snake = snake;
+                self.base_delay = c.level().delay;
+            }
+            _ => {
+                self.map = Map::new(self.map.size);
+                self.snake = Snake::new(self.map.size);
+                self.map.init_snake(self.snake.get_tail().iter());
+            }
+        }
+        self.map.grid = grid;
+        self.map.set_rng(rng);
+        self.snake.set_grid(grid);
+        self.start_recording();
+        self.elapsed = 0.;
+        self.stats = Stats::default();
 
+        self.clock.reset();
         self.time = 0.;
         self.round = 0;
         self.dirty = true;
         self.score = 0;
+        self.adjust_speed();
         self.game_over = false;
         self.game_won = false;
         self.paused = false;
+        self.update_hint();
+        self.emit(Event::Restarted);
+    }
+
+    /// plain text version of the board, the head is marked by `@`, followed by a status line
+    pub fn to_text(&self) -> String {
+        let mut rows = self.map.rows();
+        let head = self.snake.head();
+        rows[head.y as usize][head.x as usize] = '@';
+
+        let mut lines: Vec<String> = rows.iter()
+                                         .map(|r| r.iter().collect())
+                                         .collect();
+
+        let status = if self.game_over || self.game_won { self.end_text() } else { "" };
+        lines.push(format!("score: {} speed: {} {}", self.score, self.speed_level(), status).trim_end().to_string());
+        if let Some(mode) = self.mode_status() {
+            lines.push(mode);
+        }
+
+        lines.join("\r\n")
     }
 
     pub fn print_help(&self) {
@@ -0,0 +1,241 @@
+use std::io;
+use std::path::Path;
+
+use super::{Game, Cause};
+use super::campaign::{Campaign, Goal};
+use super::orientation::{Point, State, Grid};
+use super::replay::Rules;
+
+/// length of a time attack in seconds
+const TIME_ATTACK: f64 = 60.;
+/// in survival the snake grows every this many rounds
+const SURVIVAL_GROWTH: u64 = 10;
+/// in survival another ring of walls closes in every this many rounds
+const SURVIVAL_CLOSING: u64 = 50;
+
+/// the rules of the game
+#[derive(Debug, Clone)]
+pub enum Mode {
+    /// eat until the board is full
+    Classic,
+    /// levels with their own arena and goal
+    Campaign(Campaign),
+    /// eat as much as possible in 60 seconds
+    TimeAttack,
+    /// the snake grows without food and the walls close in
+    Survival,
+}
+
+impl Mode {
+    /// in seconds of game time
+    pub fn time_limit(&self) -> Option<f64> {
+        match *self {
+            Mode::Campaign(ref c) => c.level().time_limit,
+            Mode::TimeAttack => Some(TIME_ATTACK),
+            Mode::Classic | Mode::Survival => None,
+        }
+    }
+
+    /// what a replay needs to know about the mode
+    pub fn rules(&self) -> Rules {
+        match *self {
+            Mode::Classic => Rules::Classic,
+            Mode::Campaign(ref c) => Rules::Campaign(c.number()),
+            Mode::TimeAttack => Rules::TimeAttack,
+            Mode::Survival => Rules::Survival,
+        }
+    }
+
+    /// the mode of a replay, the level of a campaign is taken from the directory `levels`
+    pub fn from_rules<P: AsRef<Path>>(rules: Rules, grid: Grid, levels: P) -> io::Result<Mode> {
+        Ok(match rules {
+            Rules::Classic => Mode::Classic,
+            Rules::Campaign(level) => {
+                let mut campaign = Campaign::load(levels, grid)?;
+                campaign.replay_level(level)?;
+                Mode::Campaign(campaign)
+            }
+            Rules::TimeAttack => Mode::TimeAttack,
+            Rules::Survival => Mode::Survival,
+        })
+    }
+}
+
+impl Game {
+    /// let the time of the round pass, returns true if the time limit ran out and the game ended
+    fn out_of_time(&mut self) -> bool {
+        if let Some(limit) = self.mode.time_limit() {
+            self.elapsed += self.delay();
+            if self.elapsed > limit {
+                self.game_over(Cause::TimeUp);
+                return true
+            }
+        }
+        false
+    }
+
+    /// the recording stops before the round, in which the time ran out
+    pub fn end_replay(&mut self) {
+        self.recorded_delay();
+        if let Some(limit) = self.mode.time_limit() {
+            if !self.game_over && self.elapsed + self.delay() > limit {
+                self.out_of_time();
+            }
+        }
+    }
+
+    /// apply the rules of the mode before the snake moves, returns false if the game ended
+    pub fn mode_tick(&mut self) -> bool {
+        if self.out_of_time() {
+            return false
+        }
+
+        if let Mode::Survival = self.mode {
+            if self.round > 0 && self.round % SURVIVAL_GROWTH == 0 {
+                self.snake.feed();
+            }
+            if self.round > 0 && self.round % SURVIVAL_CLOSING == 0 {
+                self.close_in();
+            }
+        }
+
+        true
+    }
+
+    /// apply the rules of the mode after the snake ate
+    pub fn mode_fed(&mut self) {
+        let goal = match self.mode {
+            Mode::Campaign(ref c) => c.level().goal,
+            _ => return,
+        };
+
+        if goal.reached(self.snake.length, self.score) {
+            self.level_complete();
+        }
+    }
+
+    fn level_complete(&mut self) {
+        let next = match self.mode {
+            Mode::Campaign(ref mut c) => c.advance(),
+            _ => false,
+        };
+
+        if next {
+            // the statistics cover the whole campaign
+            let stats = self.stats.clone();
+            self.restart();
+            self.stats = stats;
+        } else {
+            self.game_won();
+        }
+    }
+
+    /// build walls on all free tiles of the outer rings
+    fn close_in(&mut self) {
+        let food = self.map.food;
+        let (w, h) = (self.map.size.0 as i32, self.map.size.1 as i32);
+        // leave some space to move
+        let rings = ((self.round / SURVIVAL_CLOSING) as i32).min(w.min(h) / 2 - 2);
+
+        for k in 0..rings {
+            for x in k..w-k {
+                for y in k..h-k {
+                    let p = Point::new(x, y);
+                    let ring = x == k || y == k || x == w-1-k || y == h-1-k;
+                    // food under the new walls is placed somewhere else
+                    if ring && (self.map.at(&p) == State::Empty || self.map.at(&p) == State::Food) {
+                        self.map.add_wall(p);
+                    }
+                }
+            }
+        }
+
+        if self.map.food != food {
+            self.relocated_food();
+        }
+    }
+
+    /// status line of the mode, e.g., the goal and the remaining time
+    pub fn mode_status(&self) -> Option<String> {
+        let remaining = self.mode.time_limit()
+                                 .map(|limit| format!("{:.0} s", (limit - self.elapsed).max(0.)));
+
+        match self.mode {
+            Mode::Classic => None,
+            Mode::Campaign(ref c) => {
+                let level = c.level();
+                let goal = match level.goal {
+                    Goal::Length(l) => format!("length {}/{}", self.snake.length, l),
+                    Goal::Score(s) => format!("score {}/{}", self.score, s),
+                };
+                let mut status = format!("{}/{} {}: {}", c.number(), c.len(), level.name, goal);
+                if let Some(r) = remaining {
+                    status.push_str(&format!(", {}", r));
+                }
+                Some(status)
+            }
+            Mode::TimeAttack => remaining,
+            Mode::Survival => Some(format!("round {}", self.round)),
+        }
+    }
+
+    /// text shown when the game ended
+    pub fn end_text(&self) -> &'static str {
+        let timeout = self.mode.time_limit().map_or(false, |limit| self.elapsed > limit);
+        if self.game_won {
+            "You Win!"
+        } else if timeout {
+            "Time's up!"
+        } else {
+            "Game Over!"
+        }
+    }
+}
+
+#[test]
+fn test_replay_rules() {
+    use std::env;
+    use std::fs;
+    use piston::input::keyboard::Key;
+    use super::{Autopilot, Replay, Difficulty};
+
+    let path = env::temp_dir().join("rsnake_test_replay_rules.txt");
+    let play = |mode: Mode, difficulty: Difficulty| {
+        let mut game = Game::with_mode((12, 12), mode);
+        game.seed(3);
+        game.restart();
+        game.set_autopilot(Autopilot::Smart);
+        game.set_difficulty(difficulty);
+        while !game.finished() && game.round() < 300 {
+            // speed up by hand as well
+            if game.round() == 20 {
+                game.key_press(Key::E);
+            }
+            game.tick();
+        }
+        game
+    };
+
+    let mut campaign = Campaign::load("assets/levels", Grid::Square).unwrap();
+    campaign.replay_level(7).unwrap();
+    let games = [
+        (Mode::Survival, Difficulty::Constant),
+        (Mode::TimeAttack, Difficulty::Constant),
+        (Mode::TimeAttack, Difficulty::Linear),
+        (Mode::Campaign(campaign.clone()), Difficulty::Constant),
+        (Mode::Campaign(campaign), Difficulty::Exponential),
+    ];
+    for &(ref mode, difficulty) in games.iter() {
+        let game = play(mode.clone(), difficulty);
+        game.replay().save(&path).unwrap();
+        let replay = Replay::load(&path).unwrap();
+        assert_eq!(replay, *game.replay());
+
+        let mut again = Game::from_replay(replay.clone(), Mode::from_rules(replay.rules, replay.grid, "assets/levels").unwrap());
+        while !again.finished() {
+            again.tick();
+        }
+        assert_eq!(again.to_text(), game.to_text());
+    }
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,195 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+use rand::Rng;
+use rand::distributions::Normal;
+
+use super::Game;
+use super::orientation::{Direction, State};
+
+/// number of neurons of the hidden layer
+const HIDDEN: usize = 8;
+
+/// a tiny feed forward network, one hidden layer with tanh activation,
+/// its weights are the genome, which evolves
+#[derive(Debug, Clone, PartialEq)]
+pub struct Network {
+    inputs: usize,
+    hidden: usize,
+    outputs: usize,
+    /// weights of the hidden and the output layer, each neuron has a bias as its last weight
+    weights: Vec<f64>,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid genome: {}", msg))
+}
+
+impl Network {
+    /// random weights for a network deciding between `outputs` directions
+    pub fn random<R: Rng>(outputs: usize, rng: &mut R) -> Network {
+        // danger and heading for every direction and the way to the food
+        let inputs = 2 * outputs + 2;
+        let n = HIDDEN * (inputs + 1) + outputs * (HIDDEN + 1);
+        let normal = Normal::new(0., 1.);
+        Network {
+            inputs,
+            hidden: HIDDEN,
+            outputs,
+            weights: (0..n).map(|_| rng.sample(normal)).collect(),
+        }
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.outputs
+    }
+
+    pub fn forward(&self, input: &[f64]) -> Vec<f64> {
+        let layer = |weights: &[f64], input: &[f64], n: usize| -> Vec<f64> {
+            weights.chunks(input.len() + 1)
+                   .take(n)
+                   .map(|w| {
+                       let sum: f64 = w.iter().zip(input).map(|(a, b)| a * b).sum();
+                       (sum + w[input.len()]).tanh()
+                   })
+                   .collect()
+        };
+
+        let split = self.hidden * (self.inputs + 1);
+        let hidden = layer(&self.weights[..split], input, self.hidden);
+        layer(&self.weights[split..], &hidden, self.outputs)
+    }
+
+    /// every weight is taken from either parent
+    pub fn crossover<R: Rng>(&self, other: &Network, rng: &mut R) -> Network {
+        let weights = self.weights.iter()
+                                  .zip(&other.weights)
+                                  .map(|(&a, &b)| if rng.gen() { a } else { b })
+                                  .collect();
+        Network {
+            weights,
+            ..self.clone()
+        }
+    }
+
+    /// add gaussian noise of width `sigma` to a fraction `rate` of the weights
+    pub fn mutate<R: Rng>(&mut self, rate: f64, sigma: f64, rng: &mut R) {
+        let normal = Normal::new(0., sigma);
+        for w in self.weights.iter_mut() {
+            if rng.gen::<f64>() < rate {
+                *w += rng.sample(normal);
+            }
+        }
+    }
+
+    /// the file consists of two lines: the number of inputs, hidden neurons and outputs
+    /// followed by all weights
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        writeln!(file, "{} {} {}", self.inputs, self.hidden, self.outputs)?;
+        let weights: Vec<String> = self.weights.iter()
+                                               .map(|w| w.to_string())
+                                               .collect();
+        writeln!(file, "{}", weights.join(" "))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Network> {
+        let file = File::open(path)?;
+        let mut lines = BufReader::new(file).lines();
+
+        let header = lines.next().ok_or_else(|| invalid("missing layers"))??;
+        let layers = header.split_whitespace()
+                           .map(|s| s.parse::<usize>().map_err(|_| invalid("layers need to be integers")))
+                           .collect::<io::Result<Vec<usize>>>()?;
+        if layers.len() != 3 {
+            return Err(invalid("three layers expected"))
+        }
+
+        let weights = lines.next().ok_or_else(|| invalid("missing weights"))??;
+        let weights = weights.split_whitespace()
+                             .map(|s| s.parse::<f64>().map_err(|_| invalid("weights need to be numbers")))
+                             .collect::<io::Result<Vec<f64>>>()?;
+
+        let (inputs, hidden, outputs) = (layers[0], layers[1], layers[2]);
+        if inputs != 2 * outputs + 2 {
+            return Err(invalid("the inputs do not match the outputs"))
+        }
+        if weights.len() != hidden * (inputs + 1) + outputs * (hidden + 1) {
+            return Err(invalid("the number of weights does not match the layers"))
+        }
+
+        Ok(Network {
+            inputs,
+            hidden,
+            outputs,
+            weights,
+        })
+    }
+}
+
+impl Game {
+    /// what the network sees: danger in every direction, the way to the food and the heading
+    fn neural_input(&self) -> Vec<f64> {
+        let head = self.snake.head();
+        let directions = self.map.grid.directions();
+
+        let mut input: Vec<f64> = directions.iter()
+                                            .map(|&d| match self.map.predict(&self.map.neighbor(head, d), 1) {
+                                                State::Empty | State::Food => 0.,
+                                                _ => 1.,
+                                            })
+                                            .collect();
+
+        // shortest way over the periodic boundaries
+        let wrap = |d: i32, n: u32| {
+            let n = n as i32;
+            let d = (d % n + n) % n;
+            if d > n / 2 { d - n } else { d }
+        };
+        let target = self.map.get_food();
+        input.push(wrap(target.x - head.x, self.map.size.0) as f64 / self.map.size.0 as f64);
+        input.push(wrap(target.y - head.y, self.map.size.1) as f64 / self.map.size.1 as f64);
+
+        input.extend(directions.iter().map(|&d| if d == self.snake.direction { 1. } else { 0. }));
+
+        input
+    }
+
+    /// head into the direction the network likes best, falls back to the smart autopilot
+    /// without a network or if it was trained on another grid
+    pub fn neural_autopilot(&mut self) {
+        let direction = match self.network {
+            Some(ref net) if net.outputs() == self.map.grid.directions().len() => {
+                let output = net.forward(&self.neural_input());
+                let directions: &[Direction] = self.map.grid.directions();
+                directions.iter()
+                          .zip(output)
+                          .filter(|&(&d, _)| !self.snake.reverse(d))
+                          .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                          .map(|(&d, _)| d)
+            }
+            _ => None,
+        };
+
+        match direction {
+            Some(d) => self.snake.turn(d),
+            None => self.smart_autopilot(),
+        }
+    }
+}
+
+#[test]
+fn test_save_load() {
+    let net = Network::random(4, &mut ::rand::thread_rng());
+    let path = ::std::env::temp_dir().join("rsnake_test_genome.txt");
+    net.save(&path).unwrap();
+    assert_eq!(Network::load(&path).unwrap(), net);
+
+    // the network of the hexagonal grid with the inputs of the square one
+    let mut net = Network::random(6, &mut ::rand::thread_rng());
+    net.inputs = 10;
+    net.weights.truncate(HIDDEN * (net.inputs + 1) + net.outputs * (HIDDEN + 1));
+    net.save(&path).unwrap();
+    assert!(Network::load(&path).is_err());
+}
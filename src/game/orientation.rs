@@ -1,10 +1,10 @@
 use std::ops::Add;
-use std::collections::{HashSet, BinaryHeap};
+use std::collections::{HashSet, BinaryHeap, VecDeque};
 use std::cmp::Ordering;
 
 use super::map::Map;
 
-#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy)]
+#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy, Serialize)]
 pub struct Point {
     pub x: i32,
     pub y: i32,
@@ -20,11 +20,17 @@ impl Add<Direction> for Point {
     type Output = Point;
 
     fn add(self, other: Direction) -> Point {
+        // on the hex grid odd rows are shifted half a tile to the right
+        let odd = self.y & 1;
         match other {
             Direction::N => Point {x: self.x , y: self.y - 1},
             Direction::S => Point {x: self.x , y: self.y + 1},
             Direction::E => Point {x: self.x + 1 , y: self.y},
             Direction::W => Point {x: self.x - 1 , y: self.y},
+            Direction::NE => Point {x: self.x + odd, y: self.y - 1},
+            Direction::NW => Point {x: self.x + odd - 1, y: self.y - 1},
+            Direction::SE => Point {x: self.x + odd, y: self.y + 1},
+            Direction::SW => Point {x: self.x + odd - 1, y: self.y + 1},
         }
     }
 }
@@ -41,13 +47,13 @@ impl Add for Point {
 }
 
 impl Point {
-    pub fn neighbors(&self) -> Neighbor {
-        Neighbor::direct(*self)
-    }
-
     pub fn neighbors2(&self) -> Neighbor {
         Neighbor::diagonal(*self)
     }
+
+    pub fn hex_neighbors(&self) -> Neighbor {
+        Neighbor::hex(*self)
+    }
 }
 
 pub struct Neighbor {
@@ -56,12 +62,16 @@ pub struct Neighbor {
 }
 
 impl Neighbor {
-    fn direct(pos: Point) -> Neighbor {
+    fn diagonal(pos: Point) -> Neighbor {
         let offsets = vec![
             Point::new(0, 1),
+            Point::new(1, 1),
             Point::new(1, 0),
+            Point::new(1, -1),
             Point::new(0, -1),
+            Point::new(-1, -1),
             Point::new(-1, 0),
+            Point::new(-1, 1),
         ];
 
         Neighbor {
@@ -69,18 +79,16 @@ impl Neighbor {
             offsets
         }
     }
+}
 
-    fn diagonal(pos: Point) -> Neighbor {
-        let offsets = vec![
-            Point::new(0, 1),
-            Point::new(1, 1),
-            Point::new(1, 0),
-            Point::new(1, -1),
-            Point::new(0, -1),
-            Point::new(-1, -1),
-            Point::new(-1, 0),
-            Point::new(-1, 1),
-        ];
+impl Neighbor {
+    fn hex(pos: Point) -> Neighbor {
+        // the offsets depend on the row
+        let offsets = Grid::Hex.directions()
+                               .iter()
+                               .map(|&d| pos + d)
+                               .map(|p| Point::new(p.x - pos.x, p.y - pos.y))
+                               .collect();
 
         Neighbor {
             pos,
@@ -100,12 +108,67 @@ impl Iterator for Neighbor {
     }
 }
 
-#[derive(Hash, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Hash, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum Direction {
     N,
     S,
     W,
     E,
+    // only on the hex grid
+    NE,
+    NW,
+    SE,
+    SW,
+}
+
+/// the shape of the tiles
+#[derive(Hash, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grid {
+    Square,
+    /// pointy topped hexagons, odd rows are shifted half a tile to the right,
+    /// the height needs to be even to wrap around
+    Hex,
+}
+
+const SQUARE_DIRECTIONS: [Direction; 4] = [Direction::E, Direction::N, Direction::W, Direction::S];
+const HEX_DIRECTIONS: [Direction; 6] = [
+    Direction::E, Direction::NE, Direction::NW, Direction::W, Direction::SW, Direction::SE
+];
+
+impl Grid {
+    /// all directions of movement, counterclockwise starting in the east
+    pub fn directions(&self) -> &'static [Direction] {
+        match *self {
+            Grid::Square => &SQUARE_DIRECTIONS,
+            Grid::Hex => &HEX_DIRECTIONS,
+        }
+    }
+
+    fn rotate(&self, dir: Direction, steps: usize) -> Direction {
+        let dirs = self.directions();
+        match dirs.iter().position(|&d| d == dir) {
+            Some(i) => dirs[(i + steps) % dirs.len()],
+            None => dir,
+        }
+    }
+
+    pub fn left(&self, dir: Direction) -> Direction {
+        self.rotate(dir, 1)
+    }
+
+    pub fn right(&self, dir: Direction) -> Direction {
+        self.rotate(dir, self.directions().len() - 1)
+    }
+}
+
+/// number of steps between two tiles of the hex grid, without periodic boundaries
+pub fn hex_distance(p1: &Point, p2: &Point) -> i32 {
+    // convert to axial coordinates
+    let q = |p: &Point| p.x - (p.y - (p.y & 1)) / 2;
+    let dq = q(p1) - q(p2);
+    let dr = p1.y - p2.y;
+
+    (dq.abs() + dr.abs() + (dq + dr).abs()) / 2
 }
 
 #[derive(Hash, Debug, Clone, Copy, PartialEq, Eq)]
@@ -114,9 +177,13 @@ pub enum State {
     Wall,
     Snake,
     Food,
+    /// one of a pair of tiles, entering one leaves the other
+    Portal,
+    /// currently occupied by a moving obstacle
+    Hazard,
 }
 
-#[derive(Hash, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Hash, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum Reachable {
     Yes,
     No,
@@ -126,14 +193,17 @@ pub enum Reachable {
 struct Thingy {
     distance: i32,
     pos: Point,
+    /// rounds until the snake reaches this tile on the way found so far
+    ahead: u64,
 }
 
 impl Thingy {
-    fn new(point: &Point, target: &Point, map: &Map) -> Thingy {
+    fn new(point: &Point, target: &Point, ahead: u64, map: &Map) -> Thingy {
         Thingy {
             // - because the heap is a max-heap, but we want a min heap
-            distance: -map.manhattan(point, target),
-            pos: *point
+            distance: -map.distance(point, target),
+            pos: *point,
+            ahead,
         }
     }
 }
@@ -150,13 +220,15 @@ impl Ord for Thingy {
     }
 }
 
+/// whether the snake can get from `start`, which it reaches in the next round, to `target`
+/// avoiding the predicted positions of hazards
 pub fn best_first_search(start: &Point, target: &Point, map: &Map) -> Reachable {
     let mut visited: HashSet<Point> = HashSet::new();
     let mut tmp_visited: Vec<Point> = Vec::new();
     let mut q: BinaryHeap<Thingy> = BinaryHeap::new();
 
     visited.insert(*start);
-    q.push(Thingy::new(start, target, map));
+    q.push(Thingy::new(start, target, 1, map));
 
     loop {
         let nearest = match q.pop() {
@@ -175,18 +247,25 @@ pub fn best_first_search(start: &Point, target: &Point, map: &Map) -> Reachable
             panic!();
         }
 
-        for n in nearest.pos
-                        .neighbors()
-                        .filter(|x| !visited.contains(&map.normalize(x))
-                                 && match map.at(x) {
-                                        State::Wall | State::Snake => false,
-                                        State::Food | State::Empty => true
-                                    }
-                               )
+        // portals connect tiles, which are not adjacent
+        let neighbors = map.grid.directions()
+                                .iter()
+                                .map(|&d| map.neighbor(&nearest.pos, d))
+                                .collect::<Vec<Point>>();
+        if neighbors.contains(&map.normalize(target)) {
+            return Reachable::Yes
+        }
+
+        for n in neighbors.into_iter()
+                          .filter(|x| !visited.contains(x)
+                                   && match map.predict(x, nearest.ahead + 1) {
+                                          State::Wall | State::Snake | State::Portal | State::Hazard => false,
+                                          State::Food | State::Empty => true
+                                      }
+                                 )
         {
-            let n = map.normalize(&n);
             tmp_visited.push(n);
-            q.push(Thingy::new(&n, target, map))
+            q.push(Thingy::new(&n, target, nearest.ahead + 1, map))
         }
 
         for i in &tmp_visited {
@@ -195,3 +274,61 @@ pub fn best_first_search(start: &Point, target: &Point, map: &Map) -> Reachable
         tmp_visited.clear();
     }
 }
+
+/// number of tiles the snake can reach from `start`, which it reaches in the next round,
+/// avoiding the predicted positions of hazards, counting stops at `limit`
+pub fn flood_fill(start: &Point, map: &Map, limit: usize) -> usize {
+    let free = |p: &Point, ahead: u64| match map.predict(p, ahead) {
+        State::Wall | State::Snake | State::Portal | State::Hazard => false,
+        State::Food | State::Empty => true
+    };
+
+    let start = map.normalize(start);
+    if !free(&start, 1) {
+        return 0
+    }
+
+    let mut visited: HashSet<Point> = HashSet::new();
+    let mut q: VecDeque<(Point, u64)> = VecDeque::new();
+    visited.insert(start);
+    q.push_back((start, 1));
+
+    // breadth first, such that `ahead` is the earliest arrival
+    while let Some((p, ahead)) = q.pop_front() {
+        for &d in map.grid.directions() {
+            if visited.len() >= limit {
+                return limit
+            }
+            let n = map.neighbor(&p, d);
+            if !visited.contains(&n) && free(&n, ahead + 1) {
+                visited.insert(n);
+                q.push_back((n, ahead + 1));
+            }
+        }
+    }
+
+    visited.len()
+}
+
+#[test]
+fn test_hex() {
+    for p in &[Point::new(3, 4), Point::new(3, 5), Point::new(0, -1)] {
+        assert!(p.hex_neighbors().all(|n| hex_distance(p, &n) == 1));
+    }
+    assert_eq!(hex_distance(&Point::new(0, 0), &Point::new(2, 4)), 4);
+    assert_eq!(Grid::Hex.left(Direction::SE), Direction::E);
+    assert_eq!(Grid::Hex.right(Direction::E), Direction::SE);
+}
+
+#[test]
+fn test_flood_fill() {
+    let mut map = Map::new((5, 5));
+    for x in 0..5 {
+        map.add_wall(Point::new(x, 0));
+        map.add_wall(Point::new(x, 2));
+    }
+    // the row in between is closed by the walls and the periodic boundaries
+    assert_eq!(flood_fill(&Point::new(0, 1), &map, 100), 5);
+    assert_eq!(flood_fill(&Point::new(0, 1), &map, 3), 3);
+    assert_eq!(flood_fill(&Point::new(0, 0), &map, 100), 0);
+}
@@ -0,0 +1,123 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+use super::Game;
+use super::orientation::State;
+use super::env::Turn;
+
+/// danger and a step closer to the food, for going straight, left and right
+const STATES: usize = 1 << 6;
+const ACTIONS: usize = 3;
+
+/// expected return of every action in every state
+#[derive(Debug, Clone, PartialEq)]
+pub struct QTable {
+    values: Vec<[f64; ACTIONS]>,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid q-table: {}", msg))
+}
+
+fn argmax(values: &[f64; ACTIONS]) -> usize {
+    (0..ACTIONS).fold(0, |best, i| if values[i] > values[best] { i } else { best })
+}
+
+impl Default for QTable {
+    fn default() -> QTable {
+        QTable {
+            values: vec![[0.; ACTIONS]; STATES],
+        }
+    }
+}
+
+impl QTable {
+    /// the action with the highest expected return, going straight if undecided
+    pub fn best(&self, state: usize) -> Turn {
+        Turn::from_index(argmax(&self.values[state])).unwrap()
+    }
+
+    /// move the estimate towards the reward plus the discounted value of the next state,
+    /// which is worthless if the episode is over
+    pub fn update(&mut self, state: usize, turn: Turn, reward: f64, next: Option<usize>, alpha: f64, gamma: f64) {
+        let future = next.map(|n| self.values[n][argmax(&self.values[n])])
+                         .unwrap_or(0.);
+        let q = &mut self.values[state][turn as usize];
+        *q += alpha * (reward + gamma * future - *q);
+    }
+
+    /// one line per state with the values of going straight, left and right
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        for v in &self.values {
+            writeln!(file, "{} {} {}", v[0], v[1], v[2])?;
+        }
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<QTable> {
+        let file = File::open(path)?;
+        let mut values = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let v = line?.split_whitespace()
+                         .map(|s| s.parse::<f64>().map_err(|_| invalid("values need to be numbers")))
+                         .collect::<io::Result<Vec<f64>>>()?;
+            if v.len() != ACTIONS {
+                return Err(invalid("three values per state expected"))
+            }
+            values.push([v[0], v[1], v[2]]);
+        }
+
+        if values.len() != STATES {
+            return Err(invalid("wrong number of states"))
+        }
+
+        Ok(QTable {
+            values
+        })
+    }
+}
+
+impl Game {
+    /// compact state for tabular learning, works on every grid
+    ///
+    /// for going straight, left and right: whether the snake would collide
+    /// and whether it would get closer to the food
+    pub fn q_state(&self) -> usize {
+        let head = self.snake.head();
+        let food = self.map.get_food();
+        let distance = self.map.distance(head, food);
+
+        let mut state = 0;
+        for (i, &d) in [self.snake.direction, self.snake.left(), self.snake.right()].iter().enumerate() {
+            let next = self.map.neighbor(head, d);
+            let danger = match self.map.predict(&next, 1) {
+                State::Empty | State::Food => false,
+                _ => true,
+            };
+            let closer = self.map.distance(&next, food) < distance;
+            state |= (danger as usize) << (2 * i) | (closer as usize) << (2 * i + 1);
+        }
+
+        state
+    }
+
+    /// play greedily from the learned table, falls back to the smart autopilot without one
+    pub fn q_autopilot(&mut self) {
+        let turn = match self.q_table {
+            Some(ref table) => table.best(self.q_state()),
+            None => return self.smart_autopilot(),
+        };
+        self.steer(turn);
+    }
+}
+
+#[test]
+fn test_update() {
+    let mut table = QTable::default();
+    table.update(0, Turn::Left, 1., None, 0.5, 0.9);
+    assert_eq!(table.best(0), Turn::Left);
+    table.update(1, Turn::Right, 0., Some(0), 1., 0.9);
+    assert_eq!(table.values[1][Turn::Right as usize], 0.45);
+}
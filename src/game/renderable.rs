@@ -6,6 +6,9 @@ use graphics::character::CharacterCache;
 use super::Game;
 use super::snake::Snake;
 use super::map::Map;
+use super::orientation::{Point, Grid, State};
+use super::hint::Hint;
+use super::stats::Stats;
 
 fn render_text<C, G>(text: &str, font_size: u32, pos: (i32, i32), color: &str, c: Context, gfx: &mut G, glyphs: &mut C)
     where C: CharacterCache, G: Graphics<Texture=C::Texture>
@@ -19,7 +22,7 @@ fn render_text<C, G>(text: &str, font_size: u32, pos: (i32, i32), color: &str, c
     );
 }
 
-fn render_score<C, G>(score: i64, c: Context, gfx: &mut G, size: (u32, u32), scale: u32, glyphs: &mut C)
+fn render_score<C, G>(score: i64, level: i32, c: Context, gfx: &mut G, size: (u32, u32), scale: u32, glyphs: &mut C)
     where C: CharacterCache, G: Graphics<Texture=C::Texture>
 {
     let offset = 20;
@@ -27,7 +30,14 @@ fn render_score<C, G>(score: i64, c: Context, gfx: &mut G, size: (u32, u32), sca
     let dx = (size.0 * scale) as i32 - offset - max(1, (score as f64 + 0.9).log10().ceil() as i32) * (0.45 * font_size as f64) as i32;
     let dy = (size.1 * scale) as i32 - offset;
 
-    render_text(&format!("{}", score), font_size as u32, (dx, dy), "666666", c, gfx, glyphs)
+    render_text(&format!("{}", score), font_size as u32, (dx, dy), "666666", c, gfx, glyphs);
+
+    // speed level in small letters above the score
+    let text = format!("speed {}", level);
+    let small = scale as i32;
+    let dx = (size.0 * scale) as i32 - offset - text.len() as i32 * (0.5 * small as f64) as i32;
+    let dy = dy - font_size;
+    render_text(&text, small as u32, (dx, dy), "666666", c, gfx, glyphs)
 }
 
 fn render_game_over<C, G>(text: &str, score: i64, c: Context, gfx: &mut G, size: (u32, u32), scale: u32, glyphs: &mut C)
@@ -47,7 +57,21 @@ fn render_game_over<C, G>(text: &str, score: i64, c: Context, gfx: &mut G, size:
     render_text(&format!("{}", score), font_size as u32, (dx, dy), "ee33333", c, gfx, glyphs);
 }
 
-fn render_help<C, G>(texts: &[&str], c: Context, gfx: &mut G, size: (u32, u32), scale: u32, glyphs: &mut C)
+/// statistics in small letters below the score of `render_game_over`
+fn render_stats<C, G>(stats: &Stats, c: Context, gfx: &mut G, size: (u32, u32), scale: u32, glyphs: &mut C)
+    where C: CharacterCache, G: Graphics<Texture=C::Texture>
+{
+    let font_size = scale as i32;
+    let center = ((size.0 * scale) as f64 / 2.) as i32;
+    let top = 20 + 15 * font_size;
+    for (n, line) in stats.lines().iter().enumerate() {
+        let dx = center - (0.45 * line.len() as f64 / 2. * font_size as f64) as i32;
+        let dy = top + n as i32 * 3 * font_size / 2;
+        render_text(line, font_size as u32, (dx, dy), "666666", c, gfx, glyphs);
+    }
+}
+
+fn render_help<C, G>(texts: &[String], c: Context, gfx: &mut G, size: (u32, u32), scale: u32, glyphs: &mut C)
     where C: CharacterCache, G: Graphics<Texture=C::Texture>
 {
     rectangle(color::hex("cccccc"),
@@ -77,6 +101,127 @@ fn render_help<C, G>(texts: &[&str], c: Context, gfx: &mut G, size: (u32, u32),
     }
 }
 
+/// position between two neighboring tiles, `progress` 0 is `from` and 1 is `to`
+///
+/// tiles which are not adjacent, i.e., at both ends of a portal, are not interpolated
+fn interpolate(from: &Point, to: &Point, progress: f64, size: (u32, u32)) -> (f64, f64) {
+    // across the periodic boundary, the neighbor is on the other side
+    let delta = |d: i32, n: u32| match d {
+        d if d == n as i32 - 1 => -1,
+        d if d == 1 - n as i32 => 1,
+        d => d,
+    };
+    let dx = delta(to.x - from.x, size.0);
+    let dy = delta(to.y - from.y, size.1);
+
+    if dx.abs() + dy.abs() != 1 {
+        return (to.x as f64, to.y as f64)
+    }
+
+    (from.x as f64 + dx as f64 * progress, from.y as f64 + dy as f64 * progress)
+}
+
+/// hexagons are narrower than squares, such that the shifted rows fit into the window
+fn hex_width(size: (u32, u32), scale: u32) -> f64 {
+    scale as f64 * size.0 as f64 / (size.0 as f64 + 0.5)
+}
+
+/// center of a tile of the hex grid in pixels
+fn hex_center(p: &Point, size: (u32, u32), scale: u32) -> (f64, f64) {
+    let width = hex_width(size, scale);
+    let shift = if p.y & 1 == 1 { 0.5 } else { 0. };
+    ((p.x as f64 + 0.5 + shift) * width, (1.5 * p.y as f64 + 1.) * width / 3f64.sqrt())
+}
+
+/// pointy topped hexagon
+fn hexagon<G: Graphics>(color: &str, center: (f64, f64), radius: f64, c: Context, gfx: &mut G) {
+    let corners: Vec<[f64; 2]> = (0..6).map(|i| {
+        let angle = (60. * i as f64 + 30.).to_radians();
+        [center.0 + radius * angle.cos(), center.1 + radius * angle.sin()]
+    }).collect();
+    polygon(color::hex(color), &corners, c.transform, gfx);
+}
+
+fn render_hex_snake<G: Graphics>(snake: &Snake, c: Context, gfx: &mut G, size: (u32, u32), scale: u32) {
+    let radius = hex_width(size, scale) / 3f64.sqrt();
+    let center = |p: &Point| hex_center(p, size, scale);
+    // only neighbors are interpolated, not across the boundary or through portals
+    let slide = |from: &Point, to: &Point| {
+        let (a, b) = (center(from), center(to));
+        if (b.0 - a.0).hypot(b.1 - a.1) > 2. * radius {
+            b
+        } else {
+            (a.0 + (b.0 - a.0) * snake.progress, a.1 + (b.1 - a.1) * snake.progress)
+        }
+    };
+
+    // every segment moves from the tile of the one behind it into its own
+    let tail = snake.get_tail();
+    let segment = |n: usize| match tail.get(n + 1).or_else(|| snake.last_end()) {
+        Some(from) => slide(from, &tail[n]),
+        None => center(&tail[n]),
+    };
+
+    let tip = max(1, snake.length as i32 - 5) as usize;
+    for n in (1..tail.len()).take(tip) {
+        hexagon("688f4e", segment(n), 0.9 * radius, c, gfx);
+    }
+
+    hexagon("8db465", segment(0), 0.98 * radius, c, gfx);
+
+    for n in tip..tail.len() {
+        let pos = segment(n);
+        let n = n - tip + 1;
+        hexagon("688f4e", pos, radius * (1. - 0.1 * n as f64), c, gfx);
+    }
+}
+
+fn render_hex_map<G: Graphics>(map: &Map, c: Context, gfx: &mut G, size: (u32, u32), scale: u32) {
+    let radius = hex_width(size, scale) / 3f64.sqrt();
+    let center = |p: &Point| hex_center(p, size, scale);
+
+    for p in map.get_walls().iter() {
+        hexagon("cccccc", center(p), radius, c, gfx);
+    }
+    for p in map.get_portals().keys() {
+        let (x, y) = center(p);
+        let r = 0.7 * radius;
+        ellipse(color::hex("3366cc"), [x - r, y - r, 2. * r, 2. * r], c.transform, gfx);
+    }
+    for p in map.get_hazards() {
+        hexagon("ee8800", center(&p), 0.8 * radius, c, gfx);
+    }
+    hexagon("ee2222", center(map.get_food()), 0.6 * radius, c, gfx);
+}
+
+/// a marker on the tile the autopilot would go to and a warning about dead ends
+fn render_hint<C, G>(hint: &Hint, map: &Map, c: Context, gfx: &mut G, size: (u32, u32), scale: u32, glyphs: &mut C)
+    where C: CharacterCache, G: Graphics<Texture=C::Texture>
+{
+    if map.at(&hint.next) == State::Empty {
+        match map.grid {
+            Grid::Square => {
+                let p = hint.next;
+                rectangle(color::hex("3c5a2a"),
+                          rectangle::square(p.x as f64 * scale as f64 + 0.3*scale as f64,
+                                            p.y as f64 * scale as f64 + 0.3*scale as f64,
+                                            scale as f64 * 0.4),
+                          c.transform, gfx
+                );
+            }
+            Grid::Hex => {
+                let radius = hex_width(size, scale) / 3f64.sqrt();
+                hexagon("3c5a2a", hex_center(&hint.next, size, scale), 0.4 * radius, c, gfx);
+            }
+        }
+    }
+
+    if let Some(rounds) = hint.dead_end {
+        let text = format!("dead end in {}", rounds);
+        render_text(&text, scale, (scale as i32 / 2, (size.1 * scale) as i32 - scale as i32 / 2), "ee3333", c, gfx, glyphs);
+    }
+}
+
 pub trait Renderable {
     fn render<C, G>(&self, c: Context, gfx: &mut G, size: (u32, u32), scale: u32, glyphs: &mut C)
         where C: CharacterCache, G: Graphics<Texture=C::Texture>;
@@ -90,62 +235,84 @@ impl Renderable for Game {
 
         // render score
         if !self.game_over {
-            render_score(self.score, c, gfx, size, scale, glyphs);
+            render_score(self.score, self.speed_level(), c, gfx, size, scale, glyphs);
         }
 
         if self.paused {
             render_help(&self.help_texts, c, gfx, size, scale, glyphs);
         } else {
             // render content
+            if let Some(hint) = self.hint() {
+                render_hint(hint, &self.map, c, gfx, size, scale, glyphs);
+            }
             self.snake.render(c, gfx, size, scale, glyphs);
             self.map.render(c, gfx, size, scale, glyphs);
 
+            if let Some(status) = self.mode_status() {
+                render_text(&status, scale, (scale as i32 / 2, scale as i32), "666666", c, gfx, glyphs);
+            }
+
             // render Game Over
             if self.game_over {
-                render_game_over("Game Over!", self.score, c, gfx, size, scale, glyphs);
+                render_game_over(self.end_text(), self.score, c, gfx, size, scale, glyphs);
             }
             if self.game_won {
                 render_game_over("You Win!", self.score, c, gfx, size, scale, glyphs);
             }
+            if self.game_over || self.game_won {
+                render_stats(&self.stats, c, gfx, size, scale, glyphs);
+            }
         }
     }
 }
 
 impl Renderable for Snake {
-    fn render<C, G>(&self, c: Context, gfx: &mut G, _: (u32, u32), scale: u32, _: &mut C)
+    fn render<C, G>(&self, c: Context, gfx: &mut G, size: (u32, u32), scale: u32, _: &mut C)
         where C: CharacterCache, G: Graphics<Texture=C::Texture>
     {
+        if self.grid() == Grid::Hex {
+            return render_hex_snake(self, c, gfx, size, scale)
+        }
+
+        // every segment slides from the tile of the one behind it into its own,
+        // the end from the tile it just left, such that the whole snake moves smoothly
+        let tail = self.get_tail();
+        let segment = |n: usize| {
+            let p = &tail[n];
+            match tail.get(n + 1).or_else(|| self.last_end()) {
+                Some(from) => interpolate(from, p, self.progress, size),
+                None => (p.x as f64, p.y as f64),
+            }
+        };
+
         let tip = max(1, self.length as i32 - 5) as usize;
-        for p in self.get_tail()
-                     .iter()
-                     .skip(1) // do not paint head
+        for n in (1..tail.len()) // do not paint head
                      .take(tip) // do not paint last 5 segments
         {
+            let pos = segment(n);
             rectangle(color::hex("688f4e"),
-                      rectangle::square(p.x as f64 * scale as f64 + 0.05*scale as f64,
-                                        p.y as f64 * scale as f64 + 0.05*scale as f64,
+                      rectangle::square(pos.0 * scale as f64 + 0.05*scale as f64,
+                                        pos.1 * scale as f64 + 0.05*scale as f64,
                                         scale as f64 * 0.9),
                       c.transform, gfx
             );
         }
         // different head color
+        let head = segment(0);
         rectangle(color::hex("8db465"),
-                  rectangle::square(self.head().x as f64 * scale as f64 + 0.01*scale as f64,
-                                    self.head().y as f64 * scale as f64 + 0.01*scale as f64,
+                  rectangle::square(head.0 * scale as f64 + 0.01*scale as f64,
+                                    head.1 * scale as f64 + 0.01*scale as f64,
                                     scale as f64 * 0.98),
                   c.transform, gfx
         );
 
         // smaller tail
-        for (n, p) in self.get_tail()
-                     .iter()
-                     .skip(tip) // do not paint head
-                     .enumerate()
-        {
-            let n = n + 1;
+        for n in tip..tail.len() {
+            let pos = segment(n);
+            let n = n - tip + 1;
             rectangle(color::hex("688f4e"),
-                      rectangle::square(p.x as f64 * scale as f64 + 0.05 * n as f64 * scale as f64,
-                                        p.y as f64 * scale as f64 + 0.05 * n as f64 * scale as f64,
+                      rectangle::square(pos.0 * scale as f64 + 0.05 * n as f64 * scale as f64,
+                                        pos.1 * scale as f64 + 0.05 * n as f64 * scale as f64,
                                         scale as f64 * (1. - 0.1 * n as f64)),
                       c.transform, gfx
             );
@@ -154,9 +321,13 @@ impl Renderable for Snake {
 }
 
 impl Renderable for Map {
-    fn render<C, G>(&self, c: Context, gfx: &mut G, _: (u32, u32), scale: u32, _: &mut C)
+    fn render<C, G>(&self, c: Context, gfx: &mut G, size: (u32, u32), scale: u32, _: &mut C)
         where C: CharacterCache, G: Graphics<Texture=C::Texture>
     {
+        if self.grid == Grid::Hex {
+            return render_hex_map(self, c, gfx, size, scale)
+        }
+
         for p in self.get_walls().iter() {
             rectangle(color::hex("cccccc"),
                       rectangle::square(p.x as f64 * scale as f64,
@@ -166,6 +337,24 @@ impl Renderable for Map {
             );
         }
 
+        for p in self.get_portals().keys() {
+            ellipse(color::hex("3366cc"),
+                    rectangle::square(p.x as f64 * scale as f64 + 0.1*scale as f64,
+                                      p.y as f64 * scale as f64 + 0.1*scale as f64,
+                                      scale as f64 * 0.8),
+                    c.transform, gfx
+            );
+        }
+
+        for p in self.get_hazards() {
+            rectangle(color::hex("ee8800"),
+                      rectangle::square(p.x as f64 * scale as f64 + 0.1*scale as f64,
+                                        p.y as f64 * scale as f64 + 0.1*scale as f64,
+                                        scale as f64 * 0.8),
+                      c.transform, gfx
+            );
+        }
+
         let p = self.get_food();
         rectangle(color::hex("ee2222"),
                   rectangle::square(p.x as f64 * scale as f64 + 0.2*scale as f64,
@@ -0,0 +1,232 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+use super::orientation::{Point, Direction, Grid};
+
+/// the mode a game was recorded in, levels of a campaign are referred to by their number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rules {
+    Classic,
+    Campaign(usize),
+    TimeAttack,
+    Survival,
+}
+
+/// everything needed to reproduce a game:
+/// where the food appeared and in which direction the snake moved in every round
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub size: (u32, u32),
+    pub grid: Grid,
+    pub rules: Rules,
+    pub food: Vec<Point>,
+    pub directions: Vec<Direction>,
+    /// the seconds per round from the given round on, whenever they changed,
+    /// such that time limits run out in the same round
+    pub delays: Vec<(u64, f64)>,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid replay: {}", msg))
+}
+
+impl Replay {
+    pub fn new(size: (u32, u32)) -> Replay {
+        Replay {
+            size,
+            grid: Grid::Square,
+            rules: Rules::Classic,
+            food: Vec::new(),
+            directions: Vec::new(),
+            delays: Vec::new(),
+        }
+    }
+
+    /// the seconds per round in `round`, if they were recorded
+    pub fn delay(&self, round: u64) -> Option<f64> {
+        self.delays.iter()
+                   .take_while(|&&(r, _)| r <= round)
+                   .last()
+                   .map(|&(_, d)| d)
+    }
+
+    /// the file consists of four lines: the size of the board followed by `hex` on the hex grid
+    /// and the mode unless it is classic, e.g., `survival` or `campaign 2`,
+    /// the positions of the food as `x,y`, the directions as a string of `NSWE`,
+    /// where the diagonal directions of the hex grid are `7913` like on a numpad,
+    /// and the changes of the speed as `round:delay`, which older replays lack
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = File::create(path)?;
+
+        let mut header = format!("{} {}", self.size.0, self.size.1);
+        if self.grid == Grid::Hex {
+            header.push_str(" hex");
+        }
+        match self.rules {
+            Rules::Classic => (),
+            Rules::Campaign(level) => header.push_str(&format!(" campaign {}", level)),
+            Rules::TimeAttack => header.push_str(" time-attack"),
+            Rules::Survival => header.push_str(" survival"),
+        }
+        writeln!(file, "{}", header)?;
+
+        let food: Vec<String> = self.food.iter()
+                                         .map(|p| format!("{},{}", p.x, p.y))
+                                         .collect();
+        writeln!(file, "{}", food.join(" "))?;
+
+        let directions: String = self.directions.iter()
+                                                .map(|d| match *d {
+                                                    Direction::N => 'N',
+                                                    Direction::S => 'S',
+                                                    Direction::W => 'W',
+                                                    Direction::E => 'E',
+                                                    Direction::NW => '7',
+                                                    Direction::NE => '9',
+                                                    Direction::SW => '1',
+                                                    Direction::SE => '3',
+                                                })
+                                                .collect();
+        writeln!(file, "{}", directions)?;
+
+        let delays: Vec<String> = self.delays.iter()
+                                             .map(|&(r, d)| format!("{}:{}", r, d))
+                                             .collect();
+        writeln!(file, "{}", delays.join(" "))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Replay> {
+        let file = File::open(path)?;
+        let mut lines = BufReader::new(file).lines();
+        let mut next_line = || lines.next().unwrap_or_else(|| Err(invalid("file is too short")));
+
+        let header = next_line()?;
+        let mut words: Vec<&str> = header.split_whitespace().collect();
+        let mut rules = Rules::Classic;
+        if let Some(i) = words.iter().position(|w| ["campaign", "time-attack", "survival"].contains(w)) {
+            rules = match (words[i], &words[i+1..]) {
+                ("time-attack", &[]) => Rules::TimeAttack,
+                ("survival", &[]) => Rules::Survival,
+                ("campaign", &[level]) => {
+                    Rules::Campaign(level.parse().map_err(|_| invalid("the level needs to be an integer"))?)
+                }
+                _ => return Err(invalid("the mode needs to be at the end of the first line")),
+            };
+            words.truncate(i);
+        }
+        let grid = if words.last() == Some(&"hex") {
+            words.pop();
+            Grid::Hex
+        } else {
+            Grid::Square
+        };
+        let size: Vec<u32> = words.iter()
+                                  .map(|s| s.parse::<u32>())
+                                  .collect::<Result<_, _>>()
+                                  .map_err(|_| invalid("size needs to be two integers"))?;
+        if size.len() != 2 {
+            return Err(invalid("size needs to be two integers"))
+        }
+
+        let mut food = Vec::new();
+        for f in next_line()?.split_whitespace() {
+            let coords: Vec<i32> = f.split(',')
+                                    .map(|s| s.parse::<i32>())
+                                    .collect::<Result<_, _>>()
+                                    .map_err(|_| invalid("food needs to be given as x,y"))?;
+            if coords.len() != 2 {
+                return Err(invalid("food needs to be given as x,y"))
+            }
+            food.push(Point::new(coords[0], coords[1]));
+        }
+        if food.is_empty() {
+            return Err(invalid("there needs to be at least one food"))
+        }
+
+        let mut directions = Vec::new();
+        for c in next_line()?.trim().chars() {
+            let d = match c {
+                'N' => Direction::N,
+                'S' => Direction::S,
+                'W' => Direction::W,
+                'E' => Direction::E,
+                '7' => Direction::NW,
+                '9' => Direction::NE,
+                '1' => Direction::SW,
+                '3' => Direction::SE,
+                _ => return Err(invalid("directions need to be one of NSWE7913")),
+            };
+            directions.push(d);
+        }
+
+        let mut delays = Vec::new();
+        if let Some(line) = lines.next() {
+            for d in line?.split_whitespace() {
+                let mut parts = d.splitn(2, ':');
+                let round = parts.next().and_then(|r| r.parse::<u64>().ok());
+                let delay = parts.next().and_then(|d| d.parse::<f64>().ok());
+                match (round, delay) {
+                    (Some(r), Some(d)) => delays.push((r, d)),
+                    _ => return Err(invalid("speed changes need to be given as round:delay")),
+                }
+            }
+        }
+
+        Ok(Replay {
+            size: (size[0], size[1]),
+            grid,
+            rules,
+            food,
+            directions,
+            delays,
+        })
+    }
+}
+
+#[test]
+fn test_seeded() {
+    use super::{Game, Mode, Autopilot};
+
+    // seeded after the recording started, like on the command line
+    let mut game = Game::new((10, 10));
+    game.seed(5);
+    game.set_autopilot(Autopilot::Smart);
+    for _ in 0..100 {
+        game.tick();
+    }
+
+    let mut again = Game::from_replay(game.replay().clone(), Mode::Classic);
+    while !again.finished() {
+        again.tick();
+    }
+    assert_eq!(again.to_text(), game.to_text());
+}
+
+#[test]
+fn test_hazard_over_food() {
+    use super::{Game, Mode, Campaign};
+    use super::campaign::Level;
+
+    // a patrol sweeps all rows but the last one, where the snake goes around in circles
+    let waypoints: Vec<String> = (0..9).flat_map(|y| if y % 2 == 0 { vec![(0, y), (9, y)] } else { vec![(9, y), (0, y)] })
+                                       .map(|(x, y)| format!("{},{}", x, y))
+                                       .collect();
+    let arena = format!("{}.....S....", "..........\n".repeat(9));
+    let level = Level::parse(&format!("goal = length 50\npatrol = {}\n\n{}", waypoints.join(" "), arena)).unwrap();
+
+    let mut game = Game::with_mode((10, 10), Mode::Campaign(Campaign::new(vec![level.clone()])));
+    game.seed(2);
+    for _ in 0..300 {
+        game.tick();
+    }
+    assert!(!game.finished());
+    // some food was run over
+    assert!(game.replay().food.len() as i64 > game.score + 1);
+
+    let mut again = Game::from_replay(game.replay().clone(), Mode::Campaign(Campaign::new(vec![level])));
+    while !again.finished() {
+        again.tick();
+    }
+    assert_eq!(again.to_text(), game.to_text());
+}
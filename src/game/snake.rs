@@ -1,31 +1,50 @@
 use std::collections::VecDeque;
 
-use super::orientation::{Point, Direction};
+use super::orientation::{Point, Direction, Grid};
 use super::map::Map;
 
+#[derive(Clone)]
 pub struct Snake {
     tail: VecDeque<Point>,
+    /// turns requested by the player, which are not yet applied
+    turns: VecDeque<Direction>,
     pub direction: Direction,
     pub last_direction: Direction,
     pub length: usize,
-    size: (u32, u32),
+    /// the segment which was removed in the last step, if the snake did not grow
+    last_end: Option<Point>,
+    /// fraction of the way to the next tile, used for smooth rendering
+    pub progress: f64,
+    grid: Grid,
 }
 
 impl Snake {
     pub fn new(size: (u32, u32)) -> Snake {
+        Snake::starting_at(size, Point::new(7, 5))
+    }
+
+    /// a snake heading east, with its tail to the west of `head`,
+    /// which wraps around boards smaller than the position
+    pub fn starting_at(size: (u32, u32), head: Point) -> Snake {
+        let (w, h) = (size.0 as i32, size.1 as i32);
+        let y = (head.y % h + h) % h;
         let mut init = VecDeque::new();
-        init.push_back(Point::new(7, 5));
-        init.push_back(Point::new(6, 5));
-        init.push_back(Point::new(5, 5));
+        for i in 0..3 {
+            let x = ((head.x - i) % w + w) % w;
+            init.push_back(Point::new(x, y));
+        }
 
         let length = init.len();
 
         Snake {
             tail: init,
+            turns: VecDeque::new(),
             direction: Direction::E,
             last_direction: Direction::E,
             length,
-            size,
+            last_end: None,
+            progress: 1.,
+            grid: Grid::Square,
         }
     }
 
@@ -38,35 +57,31 @@ impl Snake {
     }
 
     pub fn step(&mut self, map: &mut Map) {
-        let new_head = self.peek();
+        let new_head = self.peek(map);
         self.tail.push_front(new_head);
         map.occupy(new_head);
 
+        self.last_end = None;
         while self.tail.len() > self.length {
             let to_free = self.tail.pop_back().unwrap();
             map.free(to_free);
+            self.last_end = Some(to_free);
         }
 
         self.last_direction = self.direction;
     }
 
-    pub fn peek(&self) -> Point {
-        let mut p = *self.head() + self.direction;
-
-        // periodic boundaries
-        if p.x == self.size.0 as i32 {
-            p.x = 0;
-        } else if p.x == -1 {
-            p.x = self.size.0 as i32 - 1;
-        }
+    /// the next position of the head, through the periodic boundaries and portals
+    pub fn peek(&self, map: &Map) -> Point {
+        map.neighbor(self.head(), self.direction)
+    }
 
-        if p.y == self.size.1 as i32 {
-            p.y = 0;
-        } else if p.y == -1 {
-            p.y = self.size.1 as i32 - 1;
-        }
+    pub fn set_grid(&mut self, grid: Grid) {
+        self.grid = grid;
+    }
 
-        p
+    pub fn grid(&self) -> Grid {
+        self.grid
     }
 
     pub fn turn(&mut self, dir: Direction) {
@@ -74,32 +89,41 @@ impl Snake {
     }
 
     pub fn reverse(&self, dir: Direction) -> bool {
-        let r = match dir {
-            Direction::N => Direction::S,
-            Direction::S => Direction::N,
-            Direction::E => Direction::W,
-            Direction::W => Direction::E,
-        };
+        opposite(dir) == self.last_direction
+    }
 
-        self.last_direction == r
+    /// remember a turn to be applied in a later round, at most `depth` turns are kept
+    ///
+    /// turns are checked against the previous turn in the queue, such that
+    /// a quick sequence like N, W while heading E results in a U-turn
+    pub fn queue_turn(&mut self, dir: Direction, depth: usize) {
+        let previous = self.queued_direction();
+        if dir == previous || opposite(dir) == previous || self.turns.len() >= depth {
+            return
+        }
+        self.turns.push_back(dir);
     }
 
-    pub fn left(&self) -> Direction {
-        match self.direction {
-            Direction::N => Direction::W,
-            Direction::S => Direction::E,
-            Direction::E => Direction::N,
-            Direction::W => Direction::S,
+    /// the direction after all queued turns are applied
+    pub fn queued_direction(&self) -> Direction {
+        *self.turns.back().unwrap_or(&self.last_direction)
+    }
+
+    /// apply the oldest queued turn, should be called once per round
+    pub fn apply_queued_turn(&mut self) {
+        if let Some(dir) = self.turns.pop_front() {
+            if !self.reverse(dir) {
+                self.turn(dir);
+            }
         }
     }
 
+    pub fn left(&self) -> Direction {
+        self.grid.left(self.direction)
+    }
+
     pub fn right(&self) -> Direction {
-        match self.direction {
-            Direction::N => Direction::E,
-            Direction::S => Direction::W,
-            Direction::E => Direction::S,
-            Direction::W => Direction::N,
-        }
+        self.grid.right(self.direction)
     }
 
     pub fn turn_left(&mut self) {
@@ -119,4 +143,58 @@ impl Snake {
     pub fn get_tail(&self) -> &VecDeque<Point> {
         &self.tail
     }
+
+    /// the segment which was removed in the last step
+    pub fn last_end(&self) -> Option<&Point> {
+        self.last_end.as_ref()
+    }
+}
+
+pub fn opposite(dir: Direction) -> Direction {
+    match dir {
+        Direction::N => Direction::S,
+        Direction::S => Direction::N,
+        Direction::E => Direction::W,
+        Direction::W => Direction::E,
+        Direction::NE => Direction::SW,
+        Direction::SW => Direction::NE,
+        Direction::NW => Direction::SE,
+        Direction::SE => Direction::NW,
+    }
+}
+
+#[test]
+fn test_queued_u_turn() {
+    let mut map = Map::new((20, 20));
+    let mut snake = Snake::new((20, 20));
+
+    // heading east, north and west within one round
+    snake.queue_turn(Direction::N, 3);
+    snake.queue_turn(Direction::W, 3);
+    // reverse of west, dropped
+    snake.queue_turn(Direction::E, 3);
+
+    let start = *snake.head();
+    snake.apply_queued_turn();
+    snake.step(&mut map);
+    assert_eq!(*snake.head(), Point::new(start.x, start.y - 1));
+    snake.apply_queued_turn();
+    snake.step(&mut map);
+    assert_eq!(*snake.head(), Point::new(start.x - 1, start.y - 1));
+    assert_eq!(snake.direction, Direction::W);
+}
+
+#[test]
+fn test_small_board() {
+    let mut map = Map::new((3, 3));
+    let mut snake = Snake::new((3, 3));
+    map.init_snake(snake.get_tail().iter());
+
+    let tail: Vec<Point> = snake.get_tail().iter().cloned().collect();
+    assert!(tail.iter().all(|p| map.normalize(p) == *p));
+    assert_eq!(tail, vec![Point::new(1, 2), Point::new(0, 2), Point::new(2, 2)]);
+
+    snake.turn(Direction::N);
+    snake.step(&mut map);
+    assert_eq!(*snake.head(), Point::new(1, 1));
 }
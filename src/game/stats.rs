@@ -0,0 +1,98 @@
+use super::{Game, Autopilot};
+
+/// collected while playing, shown when the game ended
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    /// game time in seconds, the sum of the delays of all rounds
+    pub duration: f64,
+    pub rounds: u64,
+    pub foods: u64,
+    pub turns: u64,
+    /// most rounds in a row without turning
+    pub longest_straight: u64,
+    straight: u64,
+    /// seconds every autopilot steered, in the order of their first use
+    pub autopilots: Vec<(Autopilot, f64)>,
+}
+
+impl Stats {
+    pub fn rounds_per_food(&self) -> Option<f64> {
+        if self.foods == 0 {
+            None
+        } else {
+            Some(self.rounds as f64 / self.foods as f64)
+        }
+    }
+
+    fn record(&mut self, autopilot: Autopilot, delay: f64, turned: bool, ate: bool) {
+        self.duration += delay;
+        self.rounds += 1;
+        if ate {
+            self.foods += 1;
+        }
+
+        if turned {
+            self.turns += 1;
+            self.straight = 1;
+        } else {
+            self.straight += 1;
+        }
+        self.longest_straight = self.longest_straight.max(self.straight);
+
+        match self.autopilots.iter_mut().find(|&&mut (a, _)| a == autopilot) {
+            Some(&mut (_, ref mut time)) => *time += delay,
+            None => self.autopilots.push((autopilot, delay)),
+        }
+    }
+
+    /// one line per statistic for the game over screen
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("time {:.1} s, {} rounds", self.duration, self.rounds),
+            match self.rounds_per_food() {
+                Some(r) => format!("{} food, {:.1} rounds each", self.foods, r),
+                None => "no food".to_string(),
+            },
+            format!("{} turns, longest straight {}", self.turns, self.longest_straight),
+        ];
+        for &(autopilot, time) in &self.autopilots {
+            let name = match autopilot {
+                Autopilot::None => "manual".to_string(),
+                a => format!("{:?}", a).to_lowercase(),
+            };
+            lines.push(format!("{} {:.1} s", name, time));
+        }
+        lines
+    }
+}
+
+impl Game {
+    /// statistics of the current game
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// count the round which was just played
+    pub(super) fn record_round(&mut self, turned: bool, ate: bool) {
+        let (autopilot, delay) = (self.autopilot, self.delay());
+        self.stats.record(autopilot, delay, turned, ate);
+    }
+}
+
+#[test]
+fn test_record() {
+    let mut stats = Stats::default();
+    stats.record(Autopilot::None, 0.2, false, false);
+    stats.record(Autopilot::None, 0.2, true, true);
+    stats.record(Autopilot::Smart, 0.1, false, false);
+    stats.record(Autopilot::Smart, 0.1, false, true);
+    stats.record(Autopilot::None, 0.2, true, false);
+
+    assert_eq!(stats.rounds, 5);
+    assert_eq!(stats.turns, 2);
+    assert_eq!(stats.longest_straight, 3);
+    assert_eq!(stats.rounds_per_food(), Some(2.5));
+    assert_eq!(stats.autopilots.len(), 2);
+    assert!((stats.autopilots[0].1 - 0.6).abs() < 1e-9);
+    assert_eq!(stats.lines()[4], "smart 0.2 s");
+}
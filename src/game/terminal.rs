@@ -0,0 +1,236 @@
+use std::cmp::max;
+
+use termion::color;
+
+use super::Game;
+use super::snake::Snake;
+use super::map::Map;
+use super::orientation::{Point, Grid, State};
+use super::hint::Hint;
+use super::stats::Stats;
+
+/// a single character of the terminal, every tile of the board is two cells wide
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Cell {
+    ch: char,
+    fg: Option<&'static str>,
+    bg: Option<&'static str>,
+}
+
+impl Cell {
+    fn empty() -> Cell {
+        Cell {
+            ch: ' ',
+            fg: None,
+            bg: None,
+        }
+    }
+}
+
+/// text equivalent of a drawing surface
+pub struct Frame {
+    width: usize,
+    height: usize,
+    /// on the hex grid odd rows are shifted by half a tile
+    grid: Grid,
+    cells: Vec<Cell>,
+}
+
+fn rgb(hex: &str) -> color::Rgb {
+    let v = u32::from_str_radix(hex, 16).expect("invalid color");
+    color::Rgb((v >> 16) as u8, (v >> 8) as u8, v as u8)
+}
+
+impl Frame {
+    pub fn new(size: (u32, u32), grid: Grid) -> Frame {
+        let width = match grid {
+            Grid::Square => 2 * size.0 as usize,
+            Grid::Hex => 2 * size.0 as usize + 1,
+        };
+        let height = size.1 as usize;
+        Frame {
+            width,
+            height,
+            grid,
+            cells: vec![Cell::empty(); width * height],
+        }
+    }
+
+    fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        if x < self.width && y < self.height {
+            self.cells[y * self.width + x] = cell;
+        }
+    }
+
+    pub fn tile(&mut self, p: &Point, glyph: [char; 2], color: &'static str) {
+        if p.x < 0 || p.y < 0 {
+            return
+        }
+        let shift = if self.grid == Grid::Hex && p.y & 1 == 1 { 1 } else { 0 };
+        for (i, &ch) in glyph.iter().enumerate() {
+            self.set(2 * p.x as usize + shift + i, p.y as usize, Cell {
+                ch,
+                fg: Some("000000"),
+                bg: Some(color),
+            });
+        }
+    }
+
+    pub fn text(&mut self, text: &str, pos: (usize, usize), color: &'static str) {
+        for (i, ch) in text.chars().enumerate() {
+            self.set(pos.0 + i, pos.1, Cell {
+                ch,
+                fg: Some(color),
+                bg: None,
+            });
+        }
+    }
+
+    pub fn fill(&mut self, color: &'static str) {
+        for c in self.cells.iter_mut() {
+            *c = Cell {
+                ch: ' ',
+                fg: None,
+                bg: Some(color),
+            };
+        }
+    }
+
+    /// encode the frame as ANSI escape sequences, starting in the top left corner
+    pub fn to_ansi(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("{}", ::termion::cursor::Goto(1, 1)));
+
+        let mut current: Option<Cell> = None;
+        for (y, row) in self.cells.chunks(self.width).enumerate() {
+            for c in row {
+                let changed = match current {
+                    Some(cur) => cur.fg != c.fg || cur.bg != c.bg,
+                    None => true,
+                };
+                if changed {
+                    out.push_str(&format!("{}{}", color::Fg(color::Reset), color::Bg(color::Reset)));
+                    if let Some(fg) = c.fg {
+                        out.push_str(&format!("{}", color::Fg(rgb(fg))));
+                    }
+                    if let Some(bg) = c.bg {
+                        out.push_str(&format!("{}", color::Bg(rgb(bg))));
+                    }
+                    current = Some(*c);
+                }
+                out.push(c.ch);
+            }
+            out.push_str(&format!("{}{}", color::Fg(color::Reset), color::Bg(color::Reset)));
+            current = None;
+            if y + 1 < self.height {
+                out.push_str("\r\n");
+            }
+        }
+
+        out
+    }
+}
+
+pub trait TextRenderable {
+    fn render_text(&self, frame: &mut Frame, size: (u32, u32));
+}
+
+fn render_score(score: i64, level: i32, frame: &mut Frame, size: (u32, u32)) {
+    let text = format!("speed {}  {}", level, score);
+    let dx = max(0, 2 * size.0 as i32 - 1 - text.len() as i32) as usize;
+    let dy = max(0, size.1 as i32 - 1) as usize;
+    frame.text(&text, (dx, dy), "666666");
+}
+
+fn render_game_over(text: &str, score: i64, stats: &Stats, frame: &mut Frame, size: (u32, u32)) {
+    let center = size.0 as usize;
+    let dy = max(0, size.1 as i32 / 2 - 1) as usize;
+    frame.text(text, (center.saturating_sub(text.len() / 2), dy), "ee3333");
+
+    let score = format!("{}", score);
+    frame.text(&score, (center.saturating_sub(score.len() / 2), dy + 1), "ee3333");
+
+    for (n, line) in stats.lines().iter().enumerate() {
+        frame.text(line, (center.saturating_sub(line.len() / 2), dy + 3 + n), "666666");
+    }
+}
+
+fn render_help(texts: &[String], frame: &mut Frame) {
+    frame.fill("cccccc");
+
+    let dx = 2;
+    frame.text("rsnake", (dx, 1), "688f4e");
+    for (n, i) in texts.iter().enumerate() {
+        frame.text(i, (dx, 3 + n), "333333");
+    }
+}
+
+/// a marker on the tile the autopilot would go to and a warning about dead ends
+fn render_hint(hint: &Hint, map: &Map, frame: &mut Frame, size: (u32, u32)) {
+    if map.at(&hint.next) == State::Empty {
+        frame.tile(&hint.next, [':', ':'], "3c5a2a");
+    }
+
+    if let Some(rounds) = hint.dead_end {
+        let dy = max(0, size.1 as i32 - 1) as usize;
+        frame.text(&format!("dead end in {}", rounds), (0, dy), "ee3333");
+    }
+}
+
+impl TextRenderable for Game {
+    fn render_text(&self, frame: &mut Frame, size: (u32, u32)) {
+        if self.paused {
+            render_help(&self.help_texts, frame);
+        } else {
+            // render content
+            self.map.render_text(frame, size);
+            if let Some(hint) = self.hint() {
+                render_hint(hint, &self.map, frame, size);
+            }
+            self.snake.render_text(frame, size);
+
+            if let Some(status) = self.mode_status() {
+                frame.text(&status, (0, 0), "666666");
+            }
+
+            // render Game Over
+            if self.game_over {
+                render_game_over(self.end_text(), self.score, &self.stats, frame, size);
+            }
+            if self.game_won {
+                render_game_over("You Win!", self.score, &self.stats, frame, size);
+            }
+        }
+
+        // render score
+        if !self.game_over {
+            render_score(self.score, self.speed_level(), frame, size);
+        }
+    }
+}
+
+impl TextRenderable for Snake {
+    fn render_text(&self, frame: &mut Frame, _: (u32, u32)) {
+        for p in self.get_tail().iter().skip(1) {
+            frame.tile(p, ['(', ')'], "688f4e");
+        }
+        // different head color
+        frame.tile(self.head(), ['[', ']'], "8db465");
+    }
+}
+
+impl TextRenderable for Map {
+    fn render_text(&self, frame: &mut Frame, _: (u32, u32)) {
+        for p in self.get_walls().iter() {
+            frame.tile(p, ['#', '#'], "cccccc");
+        }
+        for p in self.get_portals().keys() {
+            frame.tile(p, ['{', '}'], "3366cc");
+        }
+        for p in self.get_hazards() {
+            frame.tile(&p, ['X', 'X'], "ee8800");
+        }
+
+        frame.tile(self.get_food(), ['<', '>'], "ee2222");
+    }
+}
@@ -0,0 +1,143 @@
+use std::fs::File;
+use std::io::{self, LineWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use serde_json;
+
+use super::{Game, Autopilot};
+use super::orientation::{Direction, Point, Reachable};
+
+/// a direction the autopilot looked at and what it found out
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Candidate {
+    pub direction: Direction,
+    /// whether the snake would collide in the next round
+    pub hazard: bool,
+    /// whether the end of the tail can be reached afterwards
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reachable: Option<Reachable>,
+    /// the tiles reachable afterwards, at most as many as the snake is long
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub space: Option<usize>,
+}
+
+/// everything the autopilot considered in one round
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Decision {
+    pub round: u64,
+    pub autopilot: Autopilot,
+    /// the direction before the autopilot was asked
+    pub heading: Direction,
+    /// the food or a portal on the way to it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<Point>,
+    /// the parts of the autopilot which changed or confirmed the direction, in order
+    pub branches: Vec<&'static str>,
+    pub candidates: Vec<Candidate>,
+    pub direction: Direction,
+    /// moved, ate, won or died
+    pub outcome: &'static str,
+}
+
+/// json lines with one decision per round, shared by all copies of a game
+#[derive(Clone)]
+pub struct Trace {
+    out: Arc<Mutex<LineWriter<File>>>,
+}
+
+impl Trace {
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Trace> {
+        Ok(Trace {
+            out: Arc::new(Mutex::new(LineWriter::new(File::create(path)?))),
+        })
+    }
+
+    fn write(&self, decision: &Decision) -> io::Result<()> {
+        let line = serde_json::to_string(decision)?;
+        writeln!(self.out.lock().unwrap(), "{}", line)
+    }
+}
+
+impl Game {
+    /// log why the autopilot chose its direction, every round
+    pub fn set_trace(&mut self, trace: Trace) {
+        self.trace = Some(trace);
+    }
+
+    /// start recording the decision of this round, if tracing
+    pub(super) fn begin_decision(&mut self) {
+        if self.trace.is_none() || self.autopilot == Autopilot::None {
+            return
+        }
+        self.decision = Some(Decision {
+            round: self.round,
+            autopilot: self.autopilot,
+            heading: self.snake.direction,
+            target: None,
+            branches: Vec::new(),
+            candidates: Vec::new(),
+            direction: self.snake.direction,
+            outcome: "moved",
+        });
+    }
+
+    /// add to the decision of this round, cheap if not tracing
+    pub(super) fn note<F: FnOnce(&Game, &mut Decision)>(&mut self, f: F) {
+        if let Some(mut decision) = self.decision.take() {
+            f(self, &mut decision);
+            self.decision = Some(decision);
+        }
+    }
+
+    pub(super) fn note_branch(&mut self, branch: &'static str) {
+        self.note(|_, d| d.branches.push(branch));
+    }
+
+    /// a direction the autopilot considered, with the results of its searches
+    pub(super) fn note_candidate(&mut self, direction: Direction, reachable: Option<Reachable>, space: Option<usize>) {
+        self.note(|game, d| d.candidates.push(Candidate {
+            direction,
+            hazard: game.hazard(direction),
+            reachable,
+            space,
+        }));
+    }
+
+    /// write the decision of this round with its outcome
+    pub(super) fn end_decision(&mut self, outcome: &'static str) {
+        if let Some(mut decision) = self.decision.take() {
+            decision.direction = self.snake.direction;
+            decision.outcome = outcome;
+            if let Some(ref trace) = self.trace {
+                trace.write(&decision).expect("Could not write trace");
+            }
+        }
+    }
+}
+
+#[test]
+fn test_decision() {
+    use std::env;
+    use std::fs;
+
+    let path = env::temp_dir().join("rsnake_test_trace.jsonl");
+    let mut game = Game::new((10, 10));
+    game.seed(1);
+    game.set_autopilot(Autopilot::Smart);
+    game.set_trace(Trace::create(&path).unwrap());
+    for _ in 0..5 {
+        game.tick();
+    }
+    // simulations do not write to the trace of the game
+    game.simulation().tick();
+
+    let text = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 5);
+    let last: serde_json::Value = serde_json::from_str(lines[4]).unwrap();
+    assert_eq!(last["round"], 4);
+    assert_eq!(last["autopilot"], "Smart");
+    assert!(last["branches"].as_array().unwrap().iter().any(|b| b == "find_food"));
+}
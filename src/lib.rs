@@ -0,0 +1,17 @@
+//! the simulation of rsnake, such that other frontends, e.g., the python bindings, can use it
+
+extern crate piston;
+#[cfg(feature = "frontends")]
+extern crate graphics;
+extern crate rand;
+#[cfg(feature = "frontends")]
+extern crate termion;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate toml;
+extern crate dirs;
+#[cfg(test)]
+extern crate quickcheck;
+
+pub mod game;
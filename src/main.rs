@@ -3,6 +3,15 @@ extern crate graphics;
 extern crate glutin_window;
 extern crate opengl_graphics;
 extern crate rand;
+extern crate termion;
+extern crate rusttype;
+extern crate gif;
+extern crate png;
+#[macro_use]
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
+extern crate rsnake;
 
 use glutin_window::GlutinWindow as Window;
 use piston::window::WindowSettings;
@@ -12,16 +21,120 @@ use opengl_graphics::glyph_cache::GlyphCache;
 use piston::event_loop::{Events, EventSettings};
 use piston::input::{Button, Input};
 
-mod game;
-use game::Game;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use rsnake::game;
+use game::{Game, Replay, Mode, Bindings, Grid, Autopilot, Network, QTable, Trace, Event, Cause};
 use game::renderable::Renderable;
 
 mod parse_cl;
+mod tui;
+mod raster;
+mod export;
+mod asciicast;
+mod bench;
+mod server;
+mod evolve;
+mod train_q;
+use asciicast::Asciicast;
+
+pub fn new_game(o: &parse_cl::Options) -> Game {
+    let mut game = match o.replay {
+        Some(ref path) => {
+            let replay = Replay::load(path).expect("Could not load replay");
+            let mode = Mode::from_rules(replay.rules, replay.grid, &o.levels).expect("Could not load the level of the replay");
+            Game::from_replay(replay, mode)
+        }
+        None => Game::with_mode(o.size, o.mode.clone()),
+    };
+    if o.hex {
+        game.set_grid(Grid::Hex);
+    }
+    if let Some(seed) = o.seed {
+        game.seed(seed);
+    }
+    let uses = |a: Autopilot| o.autopilot == a || o.hint == a;
+    if uses(Autopilot::Neural) {
+        game.set_network(Network::load(&o.genome).expect("Could not load genome"));
+    }
+    if uses(Autopilot::QLearning) {
+        game.set_q_table(QTable::load(&o.q_table).expect("Could not load q-table"));
+    }
+    game.set_mcts_budget(Duration::from_millis(o.mcts_budget));
+    game.set_mcts_iterations(o.mcts_iterations);
+    game.set_autopilot(o.autopilot);
+    game.set_hints(o.hint, o.hint_steps);
+    if let Some(ref path) = o.trace {
+        game.set_trace(Trace::create(path).expect("Could not create trace"));
+    }
+    game.set_queue_depth(o.queue_depth);
+    game.set_difficulty(o.difficulty);
+
+    let config = o.config.as_ref()
+                         .map(PathBuf::from)
+                         .or_else(|| Bindings::default_path().filter(|p| p.exists()));
+    if let Some(path) = config {
+        game.set_bindings(Bindings::load(path).expect("Could not load key bindings"));
+    }
+
+    game
+}
+
+/// size of the tiles, such that the board fits into `pixels`,
+/// which are chosen for the first level, but the levels of a campaign may differ in size
+pub fn fit_scale(size: (u32, u32), pixels: (u32, u32)) -> u32 {
+    (pixels.0 / size.0).min(pixels.1 / size.1).max(1)
+}
+
+/// tell the player on the command line how the game ended
+pub fn announce(event: &Event) {
+    match *event {
+        Event::Died(Cause::TimeUp) => println!("Time's up!"),
+        Event::Died(_) => println!("Game Over!"),
+        Event::Won => println!("Game Won!"),
+        _ => (),
+    }
+}
 
 fn main() {
     let o = parse_cl::parse_cl();
 
-    let mut window: Window = WindowSettings::new("RSnake", [o.size.0 * o.scale, o.size.1 * o.scale])
+    if let Some(steps) = o.benchmark {
+        bench::run(&o, steps);
+        return
+    }
+
+    if let Some(episodes) = o.train_q {
+        train_q::run(&o, episodes);
+        return
+    }
+
+    if let Some(generations) = o.evolve {
+        evolve::run(&o, generations);
+        return
+    }
+
+    if let Some(ref addr) = o.serve {
+        server::run(&o, addr).expect("Server failed");
+        return
+    }
+
+    if o.headless {
+        export::run(&o).expect("Export failed");
+        return
+    }
+
+    if o.tui {
+        tui::run(&o);
+        return
+    }
+
+    let mut game = new_game(&o);
+    game.subscribe(announce);
+    let size = game.size();
+
+    let mut window: Window = WindowSettings::new("RSnake", [size.0 * o.scale, size.1 * o.scale])
                                             .exit_on_esc(true)
                                             .decorated(false)
                                             .srgb(false)
@@ -30,20 +143,25 @@ fn main() {
 
     let mut gfx = GlGraphics::new(OpenGL::V3_2);
 
-    let mut game = Game::new(o.size);
-
     game.print_help();
 
     let texture_settings = TextureSettings::new().filter(Filter::Nearest);
     let mut glyphs = GlyphCache::new("assets/FiraSans-Regular.ttf", texture_settings)
                                 .expect("Could not load font");
 
+    let mut cast = o.export_cast.as_ref()
+                                .map(|p| Asciicast::create(p).expect("Could not create cast"));
+    let start = Instant::now();
+
     let mut events = Events::new(EventSettings::new());
     while let Some(e) = events.next(&mut window) {
         match e {
             Input::Render(args) => {
+                // always redraw, the snake moves smoothly between rounds
                 gfx.draw(args.viewport(), |c, gfx| {
-                    game.render(c, gfx, o.size, o.scale, &mut glyphs);
+                    let size = game.size();
+                    let scale = fit_scale(size, (args.width, args.height));
+                    game.render(c, gfx, size, scale, &mut glyphs);
                     game.dirty = false;
                 });
             }
@@ -53,10 +171,24 @@ fn main() {
             }
 
             Input::Update(args) => {
+                let round = game.round();
                 game.update(args.dt);
+                if let Some(ref mut c) = cast {
+                    if game.round() != round {
+                        c.frame(start.elapsed().as_secs_f64(), &game.to_text());
+                    }
+                }
             }
 
             _ => {}
         }
     }
+
+    if let Some(c) = cast {
+        c.finish().expect("Could not write cast");
+    }
+
+    if let Some(ref path) = o.record {
+        game.replay().save(path).expect("Could not save replay");
+    }
 }
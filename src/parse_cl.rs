@@ -1,11 +1,58 @@
 extern crate clap;
 
-use self::clap::{App, Arg};
+use self::clap::{App, Arg, SubCommand, Error, ErrorKind};
+
+use game::{Autopilot, Campaign, Difficulty, Grid, Mode};
+use game::env::Encoding;
+
+const AUTOPILOTS: [&str; 6] = ["stupid", "smart", "boring", "neural", "q-learning", "mcts"];
+
+fn parse_autopilot(name: Option<&str>) -> Autopilot {
+    match name {
+        Some("stupid") => Autopilot::Stupid,
+        Some("smart") => Autopilot::Smart,
+        Some("boring") => Autopilot::Boring,
+        Some("neural") => Autopilot::Neural,
+        Some("q-learning") => Autopilot::QLearning,
+        Some("mcts") => Autopilot::Mcts,
+        _ => Autopilot::None,
+    }
+}
 
 #[derive(Debug)]
 pub struct Options {
     pub size: (u32, u32),
     pub scale: u32,
+    pub tui: bool,
+    pub hex: bool,
+    pub mode: Mode,
+    pub levels: String,
+    pub config: Option<String>,
+    pub queue_depth: usize,
+    pub difficulty: Difficulty,
+    pub autopilot: Autopilot,
+    pub hint: Autopilot,
+    pub hint_steps: u64,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+    pub export_gif: Option<String>,
+    pub export_frames: Option<String>,
+    pub export_cast: Option<String>,
+    pub headless: bool,
+    pub rounds: Option<u64>,
+    pub games: u64,
+    pub seed: Option<u64>,
+    pub observation: Encoding,
+    pub benchmark: Option<u64>,
+    pub serve: Option<String>,
+    pub evolve: Option<u64>,
+    pub genome: String,
+    pub train_q: Option<u64>,
+    pub q_table: String,
+    pub mcts_budget: u64,
+    pub mcts_iterations: Option<u64>,
+    pub trace: Option<String>,
+    pub heatmap: Option<String>,
 }
 
 pub fn parse_cl() -> Options {
@@ -41,6 +88,194 @@ pub fn parse_cl() -> Options {
                     .help("size of each tile in pixel")
                     .takes_value(true)
               )
+              .arg(Arg::with_name("tui")
+                    .long("tui")
+                    .help("play in the terminal instead of an OpenGL window")
+              )
+              .arg(Arg::with_name("hex")
+                    .long("hex")
+                    .help("play on a grid of hexagons, left and right turn the snake")
+                    .conflicts_with("replay")
+              )
+              .arg(Arg::with_name("mode")
+                    .long("mode")
+                    .default_value("classic")
+                    .help("the rules of the game, the campaign continues where you left off, replays know their mode")
+                    .takes_value(true)
+                    .possible_values(&["classic", "campaign", "time-attack", "survival"])
+              )
+              .arg(Arg::with_name("levels")
+                    .long("levels")
+                    .default_value("assets/levels")
+                    .help("directory with the level files of the campaign")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("config")
+                    .long("config")
+                    .help("config file with key bindings, defaults to $XDG_CONFIG_HOME/rsnake/config.toml")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("queue-depth")
+                    .long("queue-depth")
+                    .default_value("3")
+                    .help("how many turns are remembered, if keys are pressed faster than the snake moves")
+                    .takes_value(true)
+                    .validator(|s| match s.parse::<usize>() {
+                        Ok(d) if d >= 1 => Ok(()),
+                        _ => Err("queue-depth needs to be at least 1".to_string()),
+                    })
+              )
+              .arg(Arg::with_name("difficulty")
+                    .long("difficulty")
+                    .default_value("constant")
+                    .help("how the speed increases with the score")
+                    .takes_value(true)
+                    .possible_values(&["constant", "linear", "stepped", "exponential"])
+              )
+              .arg(Arg::with_name("step")
+                    .long("step")
+                    .default_value("5")
+                    .help("number of foods between speed ups for the stepped difficulty")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("autopilot")
+                    .long("autopilot")
+                    .help("start with the autopilot enabled")
+                    .takes_value(true)
+                    .possible_values(&AUTOPILOTS)
+              )
+              .arg(Arg::with_name("hint")
+                    .long("hint")
+                    .help("show where this autopilot would go and warn about dead ends while playing manually")
+                    .takes_value(true)
+                    .possible_values(&AUTOPILOTS)
+              )
+              .arg(Arg::with_name("hint-steps")
+                    .long("hint-steps")
+                    .default_value("3")
+                    .help("how many rounds ahead to warn about dead ends")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("record")
+                    .long("record")
+                    .help("save a replay of the last game to this file")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("replay")
+                    .long("replay")
+                    .help("play back a replay file")
+                    .takes_value(true)
+                    .conflicts_with("square")
+                    .conflicts_with("width")
+                    .conflicts_with("height")
+              )
+              .arg(Arg::with_name("export-gif")
+                    .long("export-gif")
+                    .help("run without a window and write the game as an animated gif")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("export-frames")
+                    .long("export-frames")
+                    .help("run without a window and write every round as png into this directory")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("export-cast")
+                    .long("export-cast")
+                    .help("write the game as asciinema cast to this file")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("headless")
+                    .long("headless")
+                    .help("run without a window as fast as possible, implied by --export-gif and --export-frames")
+              )
+              .arg(Arg::with_name("rounds")
+                    .long("rounds")
+                    .help("stop exporting after this many rounds")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("games")
+                    .long("games")
+                    .default_value("1")
+                    .help("play this many --headless games one after another, e.g., for the --heatmap of an autopilot")
+                    .takes_value(true)
+                    .validator(|s| match s.parse::<u64>() {
+                        Ok(g) if g >= 1 => Ok(()),
+                        _ => Err("games needs to be at least 1".to_string()),
+                    })
+              )
+              .arg(Arg::with_name("seed")
+                    .long("seed")
+                    .help("seed for the random numbers, such that the game is reproducible")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("observation")
+                    .long("observation")
+                    .default_value("features")
+                    .help("what the learning environment shows the agent, egocentric is a window of 11x11 tiles")
+                    .takes_value(true)
+                    .possible_values(&["planes", "egocentric", "features"])
+              )
+              .arg(Arg::with_name("benchmark")
+                    .long("benchmark")
+                    .help("run this many steps of the learning environment with random actions and report the speed")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("serve")
+                    .long("serve")
+                    .help("host learning environments for remote agents at this address, e.g., 127.0.0.1:7878")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("evolve")
+                    .long("evolve")
+                    .help("evolve the neural autopilot for this many generations and save the best to --genome")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("genome")
+                    .long("genome")
+                    .default_value("genome.txt")
+                    .help("network of the neural autopilot")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("q-table")
+                    .long("q-table")
+                    .default_value("qtable.txt")
+                    .help("table of the q-learning autopilot")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("mcts-budget")
+                    .long("mcts-budget")
+                    .default_value("20")
+                    .help("milliseconds the tree search autopilot thinks about every move")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("mcts-iterations")
+                    .long("mcts-iterations")
+                    .help("simulations the tree search autopilot plays for every move instead of --mcts-budget, such that seeded games are reproducible")
+                    .takes_value(true)
+                    .validator(|s| match s.parse::<u64>() {
+                        Ok(i) if i >= 1 => Ok(()),
+                        _ => Err("mcts-iterations needs to be at least 1".to_string()),
+                    })
+              )
+              .arg(Arg::with_name("trace")
+                    .long("trace")
+                    .help("write why the autopilot chose its direction to this file, one json line per round")
+                    .takes_value(true)
+              )
+              .arg(Arg::with_name("heatmap")
+                    .long("heatmap")
+                    .help("count the visits of the head on every tile in --headless and --benchmark games, save them as csv or png")
+                    .takes_value(true)
+              )
+              .subcommand(SubCommand::with_name("train-q")
+                    .about("learn the table of the q-learning autopilot in headless games and save it to --q-table")
+                    .arg(Arg::with_name("episodes")
+                          .long("episodes")
+                          .default_value("10000")
+                          .help("number of games to learn from")
+                          .takes_value(true)
+                    )
+              )
               .get_matches();
 
     let mut height = matches.value_of("height")
@@ -67,8 +302,110 @@ pub fn parse_cl() -> Options {
         None => 20
     };
 
+    let tui = matches.is_present("tui");
+    let hex = matches.is_present("hex");
+    // the rows of the hex grid only wrap around with an even height
+    let y = if hex && y % 2 == 1 { y + 1 } else { y };
+    // the default is fine, since a replay is not played in this mode anyway
+    if matches.is_present("replay") && matches.occurrences_of("mode") > 0 {
+        Error::with_description("--mode can not be used with --replay, the replay knows its mode",
+                                ErrorKind::ArgumentConflict).exit();
+    }
+    let levels = matches.value_of("levels").unwrap().to_string();
+    let grid = if hex { Grid::Hex } else { Grid::Square };
+    let mode = match matches.value_of("mode") {
+        Some("campaign") => Mode::Campaign(Campaign::load(&levels, grid).expect("Could not load the levels")),
+        Some("time-attack") => Mode::TimeAttack,
+        Some("survival") => Mode::Survival,
+        _ => Mode::Classic,
+    };
+    let config = matches.value_of("config").map(String::from);
+    let queue_depth = matches.value_of("queue-depth")
+                             .unwrap()
+                             .parse::<usize>().expect("queue-depth needs to be an integer");
+
+    let step = matches.value_of("step")
+                      .unwrap()
+                      .parse::<u32>().expect("step needs to be an integer");
+    let difficulty = match matches.value_of("difficulty") {
+        Some("linear") => Difficulty::Linear,
+        Some("stepped") => Difficulty::Stepped(step),
+        Some("exponential") => Difficulty::Exponential,
+        _ => Difficulty::Constant,
+    };
+
+    let autopilot = parse_autopilot(matches.value_of("autopilot"));
+    let hint = parse_autopilot(matches.value_of("hint"));
+    let hint_steps = matches.value_of("hint-steps")
+                            .unwrap()
+                            .parse::<u64>().expect("hint-steps needs to be an integer");
+
+    let record = matches.value_of("record").map(String::from);
+    let replay = matches.value_of("replay").map(String::from);
+    let export_gif = matches.value_of("export-gif").map(String::from);
+    let export_frames = matches.value_of("export-frames").map(String::from);
+    let export_cast = matches.value_of("export-cast").map(String::from);
+    let headless = matches.is_present("headless") || export_gif.is_some() || export_frames.is_some();
+    let rounds = matches.value_of("rounds")
+                        .and_then(|s| Some(s.parse::<u64>().expect("rounds needs to be an integer")));
+    let games = matches.value_of("games")
+                       .unwrap()
+                       .parse::<u64>().expect("games needs to be an integer");
+
+    let seed = matches.value_of("seed")
+                      .and_then(|s| Some(s.parse::<u64>().expect("seed needs to be an integer")));
+    let observation = Encoding::from_name(matches.value_of("observation").unwrap()).unwrap();
+    let benchmark = matches.value_of("benchmark")
+                           .and_then(|s| Some(s.parse::<u64>().expect("benchmark needs to be an integer")));
+    let serve = matches.value_of("serve").map(String::from);
+    let evolve = matches.value_of("evolve")
+                        .and_then(|s| Some(s.parse::<u64>().expect("evolve needs to be an integer")));
+    let genome = matches.value_of("genome").unwrap().to_string();
+    let train_q = matches.subcommand_matches("train-q")
+                         .map(|m| m.value_of("episodes")
+                                   .unwrap()
+                                   .parse::<u64>().expect("episodes needs to be an integer"));
+    let q_table = matches.value_of("q-table").unwrap().to_string();
+    let mcts_budget = matches.value_of("mcts-budget")
+                             .unwrap()
+                             .parse::<u64>().expect("mcts-budget needs to be an integer");
+    let mcts_iterations = matches.value_of("mcts-iterations")
+                                 .map(|i| i.parse::<u64>().expect("mcts-iterations needs to be an integer"));
+    let trace = matches.value_of("trace").map(String::from);
+    let heatmap = matches.value_of("heatmap").map(String::from);
+
     Options {
         size: (x, y),
-        scale
+        scale,
+        tui,
+        hex,
+        mode,
+        levels,
+        config,
+        queue_depth,
+        difficulty,
+        autopilot,
+        hint,
+        hint_steps,
+        record,
+        replay,
+        export_gif,
+        export_frames,
+        export_cast,
+        headless,
+        rounds,
+        games,
+        seed,
+        observation,
+        benchmark,
+        serve,
+        evolve,
+        genome,
+        train_q,
+        q_table,
+        mcts_budget,
+        mcts_iterations,
+        trace,
+        heatmap,
     }
 }
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use graphics::{Graphics, ImageSize, DrawState};
+use graphics::character::{Character, CharacterCache};
+use graphics::types::{Color, FontSize};
+use rusttype::{Font, Scale, point};
+
+/// single channel texture, used for the glyphs of the font
+pub struct RasterTexture {
+    width: u32,
+    height: u32,
+    alpha: Vec<u8>,
+}
+
+impl ImageSize for RasterTexture {
+    fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+impl RasterTexture {
+    fn sample(&self, uv: [f32; 2]) -> f32 {
+        if self.width == 0 || self.height == 0 {
+            return 0.
+        }
+        let x = (uv[0] * self.width as f32) as i64;
+        let y = (uv[1] * self.height as f32) as i64;
+        let x = x.max(0).min(self.width as i64 - 1) as usize;
+        let y = y.max(0).min(self.height as i64 - 1) as usize;
+        self.alpha[y * self.width as usize + x] as f32 / 255.
+    }
+}
+
+/// software implementation of a piston graphics backend drawing into an RGBA buffer
+pub struct Raster {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Raster {
+    pub fn new(width: u32, height: u32) -> Raster {
+        Raster {
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// the pixels as RGBA, row by row
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    fn blend(&mut self, x: u32, y: u32, color: &Color, coverage: f32) {
+        let alpha = color[3] * coverage;
+        if alpha <= 0. {
+            return
+        }
+        let idx = ((y * self.width + x) * 4) as usize;
+        for c in 0..3 {
+            let old = self.pixels[idx + c] as f32 / 255.;
+            let new = color[c] * alpha + old * (1. - alpha);
+            self.pixels[idx + c] = (new * 255.).round().max(0.).min(255.) as u8;
+        }
+        self.pixels[idx + 3] = 255;
+    }
+
+    /// transform from normalized device coordinates to pixels
+    fn to_pixel(&self, v: &[f32; 2]) -> [f32; 2] {
+        [
+            (v[0] + 1.) / 2. * self.width as f32,
+            (1. - v[1]) / 2. * self.height as f32,
+        ]
+    }
+
+    /// fill every pixel whose center lies inside the triangle,
+    /// `shade` maps the barycentric coordinates to a coverage
+    fn fill_triangle<S>(&mut self, tri: [[f32; 2]; 3], color: &Color, shade: S)
+        where S: Fn([f32; 3]) -> f32
+    {
+        let [a, b, c] = tri;
+        let area = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+        if area == 0. {
+            return
+        }
+
+        let min_x = a[0].min(b[0]).min(c[0]).floor().max(0.) as u32;
+        let max_x = a[0].max(b[0]).max(c[0]).ceil().min(self.width as f32) as u32;
+        let min_y = a[1].min(b[1]).min(c[1]).floor().max(0.) as u32;
+        let max_y = a[1].max(b[1]).max(c[1]).ceil().min(self.height as f32) as u32;
+
+        let edge = |p: [f32; 2], q: [f32; 2], x: f32, y: f32| {
+            ((q[0] - p[0]) * (y - p[1]) - (q[1] - p[1]) * (x - p[0])) / area
+        };
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let bary = [edge(b, c, px, py), edge(c, a, px, py), edge(a, b, px, py)];
+                if bary.iter().all(|&w| w >= 0.) {
+                    let coverage = shade(bary);
+                    self.blend(x, y, color, coverage);
+                }
+            }
+        }
+    }
+}
+
+impl Graphics for Raster {
+    type Texture = RasterTexture;
+
+    fn clear_color(&mut self, color: Color) {
+        for p in self.pixels.chunks_mut(4) {
+            for c in 0..3 {
+                p[c] = (color[c] * 255.).round() as u8;
+            }
+            p[3] = 255;
+        }
+    }
+
+    fn clear_stencil(&mut self, _: u8) {}
+
+    fn tri_list<F>(&mut self, _: &DrawState, color: &[f32; 4], mut f: F)
+        where F: FnMut(&mut dyn FnMut(&[[f32; 2]]))
+    {
+        f(&mut |vertices: &[[f32; 2]]| {
+            for t in vertices.chunks(3).filter(|t| t.len() == 3) {
+                let tri = [self.to_pixel(&t[0]), self.to_pixel(&t[1]), self.to_pixel(&t[2])];
+                self.fill_triangle(tri, color, |_| 1.);
+            }
+        });
+    }
+
+    fn tri_list_uv<F>(&mut self, _: &DrawState, color: &[f32; 4], texture: &RasterTexture, mut f: F)
+        where F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]]))
+    {
+        f(&mut |vertices: &[[f32; 2]], uvs: &[[f32; 2]]| {
+            for (t, uv) in vertices.chunks(3).zip(uvs.chunks(3)).filter(|&(t, _)| t.len() == 3) {
+                let tri = [self.to_pixel(&t[0]), self.to_pixel(&t[1]), self.to_pixel(&t[2])];
+                self.fill_triangle(tri, color, |w| {
+                    let u = w[0] * uv[0][0] + w[1] * uv[1][0] + w[2] * uv[2][0];
+                    let v = w[0] * uv[0][1] + w[1] * uv[1][1] + w[2] * uv[2][1];
+                    texture.sample([u, v])
+                });
+            }
+        });
+    }
+}
+
+/// glyph cache for the software backend, rasterizes every glyph on first use
+pub struct RasterGlyphs<'f> {
+    font: Font<'f>,
+    glyphs: HashMap<(FontSize, char), ([f64; 2], [f64; 2], RasterTexture)>,
+}
+
+impl RasterGlyphs<'static> {
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<RasterGlyphs<'static>> {
+        let data = fs::read(path)?;
+        let font = Font::from_bytes(data)
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e)))?;
+
+        Ok(RasterGlyphs {
+            font,
+            glyphs: HashMap::new(),
+        })
+    }
+}
+
+fn rasterize(font: &Font, font_size: FontSize, ch: char) -> ([f64; 2], [f64; 2], RasterTexture) {
+    // same conversion from points to pixels as the OpenGL glyph cache
+    let scale = Scale::uniform((font_size as f32 * 1.333).round());
+    let glyph = font.glyph(ch).scaled(scale);
+    let advance = glyph.h_metrics().advance_width as f64;
+    let glyph = glyph.positioned(point(0., 0.));
+
+    match glyph.pixel_bounding_box() {
+        Some(bb) => {
+            let width = bb.width() as u32;
+            let height = bb.height() as u32;
+            let mut alpha = vec![0; (width * height) as usize];
+            glyph.draw(|x, y, v| {
+                alpha[(y * width + x) as usize] = (v * 255.).round() as u8;
+            });
+            (
+                [bb.min.x as f64, -bb.min.y as f64],
+                [advance, 0.],
+                RasterTexture { width, height, alpha }
+            )
+        }
+        None => (
+            [0., 0.],
+            [advance, 0.],
+            RasterTexture { width: 0, height: 0, alpha: vec![] }
+        )
+    }
+}
+
+impl<'f> CharacterCache for RasterGlyphs<'f> {
+    type Texture = RasterTexture;
+    type Error = ();
+
+    fn character<'a>(&'a mut self, font_size: FontSize, ch: char) -> Result<Character<'a, RasterTexture>, ()> {
+        let font = &self.font;
+        let &mut (offset, size, ref texture) = self.glyphs
+                                                   .entry((font_size, ch))
+                                                   .or_insert_with(|| rasterize(font, font_size, ch));
+
+        Ok(Character {
+            offset,
+            size,
+            texture,
+        })
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde_json::{self, Value};
+
+use game::{Game, Grid, Mode, Autopilot};
+use game::env::{Env, Encoding, Rewards, Turn};
+use parse_cl::Options;
+
+type Sessions = Arc<Mutex<HashMap<String, Arc<Mutex<Env>>>>>;
+
+/// one line of the protocol, see the README
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase")]
+enum Request {
+    Create {
+        session: String,
+        width: Option<u32>,
+        height: Option<u32>,
+        hex: Option<bool>,
+        observation: Option<String>,
+        rewards: Option<Rewards>,
+    },
+    Reset {
+        session: String,
+        seed: Option<u64>,
+    },
+    Step {
+        session: String,
+        action: usize,
+    },
+    Close {
+        session: String,
+    },
+}
+
+/// serve learning environments as JSON lines over TCP, every connection gets its own thread,
+/// sessions are shared between the connections, such that agents can reconnect
+pub fn run(o: &Options, addr: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    println!("listening on {}", listener.local_addr()?);
+
+    let sessions: Sessions = Arc::new(Mutex::new(HashMap::new()));
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let sessions = sessions.clone();
+        let defaults = Defaults::new(o);
+        thread::spawn(move || {
+            // a client hanging up is no reason to stop the server
+            if let Err(e) = serve(stream, &sessions, &defaults) {
+                eprintln!("connection closed: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// the settings from the command line, which a `create` does not override
+struct Defaults {
+    size: (u32, u32),
+    hex: bool,
+    observation: Encoding,
+    mode: Mode,
+    autopilot: Autopilot,
+}
+
+impl Defaults {
+    fn new(o: &Options) -> Defaults {
+        let mut mode = o.mode.clone();
+        // training does not count as progress of the player
+        if let Mode::Campaign(ref mut c) = mode {
+            c.forget_progress();
+        }
+
+        Defaults {
+            size: o.size,
+            hex: o.hex,
+            observation: o.observation,
+            mode,
+            autopilot: o.autopilot,
+        }
+    }
+}
+
+fn serve(stream: TcpStream, sessions: &Sessions, defaults: &Defaults) -> io::Result<()> {
+    let mut out = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue
+        }
+
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => handle(request, sessions, defaults),
+            Err(e) => Err(format!("invalid request: {}", e)),
+        };
+        let response = match response {
+            Ok(value) => value,
+            Err(error) => json!({"ok": false, "error": error}),
+        };
+        writeln!(out, "{}", response)?;
+    }
+
+    Ok(())
+}
+
+fn session(sessions: &Sessions, id: &str) -> Result<Arc<Mutex<Env>>, String> {
+    sessions.lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("unknown session: {}", id))
+}
+
+fn handle(request: Request, sessions: &Sessions, defaults: &Defaults) -> Result<Value, String> {
+    match request {
+        Request::Create { session, width, height, hex, observation, rewards } => {
+            let encoding = match observation {
+                Some(name) => Encoding::from_name(&name).ok_or_else(|| format!("unknown observation: {}", name))?,
+                None => defaults.observation,
+            };
+            let hex = hex.unwrap_or(defaults.hex);
+            let x = width.unwrap_or(defaults.size.0);
+            let y = height.unwrap_or(defaults.size.1);
+            // the rows of the hex grid only wrap around with an even height
+            let y = if hex && y % 2 == 1 { y + 1 } else { y };
+            if x < 3 || y < 3 {
+                return Err("the board needs at least 3x3 tiles".to_string())
+            }
+            // the length of the observations must not change within a session
+            if let Mode::Campaign(ref c) = defaults.mode {
+                if c.levels().iter().any(|l| l.size != c.level().size) {
+                    return Err("the levels of the campaign differ in size".to_string())
+                }
+            }
+
+            let mut game = Game::with_mode((x, y), defaults.mode.clone());
+            if hex {
+                game.set_grid(Grid::Hex);
+            }
+            game.set_autopilot(defaults.autopilot);
+            // the levels of a campaign have their own size
+            let size = game.size();
+            let env = Env::new(game, encoding, rewards.unwrap_or_default());
+            let len = env.observation_len();
+
+            let mut sessions = sessions.lock().unwrap();
+            if sessions.contains_key(&session) {
+                return Err(format!("session exists: {}", session))
+            }
+            sessions.insert(session, Arc::new(Mutex::new(env)));
+
+            Ok(json!({"ok": true, "size": [size.0, size.1], "observation_len": len}))
+        }
+        Request::Reset { session: id, seed } => {
+            let env = session(sessions, &id)?;
+            let observation = env.lock().unwrap().reset(seed);
+            Ok(json!({"ok": true, "observation": observation}))
+        }
+        Request::Step { session: id, action } => {
+            let turn = Turn::from_index(action).ok_or_else(|| format!("unknown action: {}", action))?;
+            let env = session(sessions, &id)?;
+            let (observation, reward, done, info) = env.lock().unwrap().step(turn);
+            Ok(json!({
+                "ok": true,
+                "observation": observation,
+                "reward": reward,
+                "done": done,
+                "info": info,
+            }))
+        }
+        Request::Close { session: id } => {
+            match sessions.lock().unwrap().remove(&id) {
+                Some(_) => Ok(json!({"ok": true})),
+                None => Err(format!("unknown session: {}", id)),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_small_board() {
+    let sessions: Sessions = Arc::new(Mutex::new(HashMap::new()));
+    let defaults = Defaults {
+        size: (20, 20),
+        hex: false,
+        observation: Encoding::Planes,
+        mode: Mode::Classic,
+        autopilot: Autopilot::None,
+    };
+    let request = |line: &str| handle(serde_json::from_str(line).unwrap(), &sessions, &defaults);
+
+    let created = request(r#"{"cmd": "create", "session": "a", "width": 3, "height": 3}"#).unwrap();
+    assert_eq!(created["size"], json!([3, 3]));
+    request(r#"{"cmd": "reset", "session": "a", "seed": 1}"#).unwrap();
+    let step = request(r#"{"cmd": "step", "session": "a", "action": 1}"#).unwrap();
+    assert_eq!(step["observation"].as_array().unwrap().len(), created["observation_len"]);
+    assert!(request(r#"{"cmd": "create", "session": "b", "width": 2, "height": 3}"#).is_err());
+}
+
+#[test]
+fn test_campaign() {
+    use game::{Campaign, Level};
+
+    let sessions: Sessions = Arc::new(Mutex::new(HashMap::new()));
+    let level = |arena: &str| Level::parse(&format!("goal = length 5\n\n{}", arena)).unwrap();
+    let request = |levels: Vec<Level>| {
+        let defaults = Defaults {
+            size: (20, 20),
+            hex: false,
+            observation: Encoding::Planes,
+            mode: Mode::Campaign(Campaign::new(levels)),
+            autopilot: Autopilot::None,
+        };
+        let line = r#"{"cmd": "create", "session": "a"}"#;
+        let response = handle(serde_json::from_str(line).unwrap(), &sessions, &defaults);
+        sessions.lock().unwrap().clear();
+        response
+    };
+
+    let created = request(vec![level("S...\n...."), level("..S.\n....")]).unwrap();
+    assert_eq!(created["size"], json!([4, 2]));
+    assert_eq!(created["observation_len"], 6 * 4 * 2);
+    assert!(request(vec![level("S...\n...."), level("..S..\n.....")]).is_err());
+}
@@ -0,0 +1,64 @@
+use rand::{Rng, SeedableRng, FromEntropy};
+use rand::rngs::SmallRng;
+
+use game::{Autopilot, QTable};
+use game::env::{Env, Encoding, Rewards, Turn};
+use parse_cl::Options;
+use new_game;
+
+/// learning rate
+const ALPHA: f64 = 0.1;
+/// discount of future rewards
+const GAMMA: f64 = 0.9;
+/// the agent never stops exploring completely
+const MIN_EPSILON: f64 = 0.01;
+
+/// learn the table of the q-learning autopilot with epsilon greedy exploration,
+/// which decreases linearly over the first 80% of the episodes
+pub fn run(o: &Options, episodes: u64) {
+    let mut rng = match o.seed {
+        Some(s) => SmallRng::seed_from_u64(s),
+        None => SmallRng::from_entropy(),
+    };
+
+    let mut game = new_game(o);
+    game.set_autopilot(Autopilot::None);
+    let mut env = Env::new(game, Encoding::Features, Rewards::default());
+    let mut table = QTable::default();
+
+    let report = (episodes / 10).max(1);
+    let mut score = 0;
+    for episode in 0..episodes {
+        let epsilon = (1. - episode as f64 / (0.8 * episodes as f64)).max(MIN_EPSILON);
+
+        env.reset(None);
+        let mut state = env.game().q_state();
+        loop {
+            let turn = if rng.gen::<f64>() < epsilon {
+                Turn::from_index(rng.gen_range(0, 3)).unwrap()
+            } else {
+                table.best(state)
+            };
+
+            let (_, reward, done, info) = env.step(turn);
+            let next = env.game().q_state();
+            // a truncated episode would have gone on
+            let future = if done && !info.truncated { None } else { Some(next) };
+            table.update(state, turn, reward, future, ALPHA, GAMMA);
+
+            if done {
+                score += info.score;
+                break
+            }
+            state = next;
+        }
+
+        if (episode + 1) % report == 0 {
+            println!("episode {}: mean score {:.2}, epsilon {:.2}",
+                     episode + 1, score as f64 / report as f64, epsilon);
+            score = 0;
+        }
+    }
+
+    table.save(&o.q_table).expect("Could not save q-table");
+}
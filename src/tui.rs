@@ -0,0 +1,94 @@
+use std::io::{Write, stdout};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use termion::event::Key as TermKey;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::screen::AlternateScreen;
+use termion::{async_stdin, clear, cursor};
+
+use piston::input::keyboard::Key;
+
+use game::parse_key;
+use game::terminal::{Frame, TextRenderable};
+use parse_cl::Options;
+use asciicast::Asciicast;
+use new_game;
+
+/// translate terminal keys to the keys the game logic understands
+fn translate(key: TermKey) -> Option<Key> {
+    let key = match key {
+        TermKey::Up => Key::Up,
+        TermKey::Down => Key::Down,
+        TermKey::Left => Key::Left,
+        TermKey::Right => Key::Right,
+        TermKey::Char(' ') => Key::Space,
+        TermKey::Char('\n') => Key::Return,
+        TermKey::Char('\t') => Key::Tab,
+        TermKey::Backspace => Key::Backspace,
+        TermKey::Char(c) => return parse_key(&c.to_string()),
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+pub fn run(o: &Options) {
+    let stdout = stdout().into_raw_mode().expect("Could not switch the terminal to raw mode");
+    let mut screen = AlternateScreen::from(stdout);
+    let mut keys = async_stdin().keys();
+
+    write!(screen, "{}{}", cursor::Hide, clear::All).unwrap();
+
+    let mut game = new_game(o);
+
+    let mut cast = o.export_cast.as_ref()
+                                .map(|p| Asciicast::create(p).expect("Could not create cast"));
+
+    let start = Instant::now();
+    let mut last = start;
+    'main: loop {
+        while let Some(Ok(key)) = keys.next() {
+            match key {
+                TermKey::Esc | TermKey::Ctrl('c') => break 'main,
+                k => if let Some(k) = translate(k) {
+                    game.key_press(k);
+                }
+            }
+        }
+
+        let now = Instant::now();
+        let dt = now - last;
+        last = now;
+
+        let round = game.round();
+        game.update(dt.as_secs_f64());
+        if let Some(ref mut c) = cast {
+            if game.round() != round {
+                c.frame((now - start).as_secs_f64(), &game.to_text());
+            }
+        }
+
+        if game.dirty {
+            let size = game.size();
+            let mut frame = Frame::new(size, game.grid());
+            game.render_text(&mut frame, size);
+            write!(screen, "{}", frame.to_ansi()).unwrap();
+            screen.flush().unwrap();
+            game.dirty = false;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    write!(screen, "{}", cursor::Show).unwrap();
+
+    if let Some(c) = cast {
+        c.finish().expect("Could not write cast");
+    }
+
+    if let Some(ref path) = o.record {
+        game.replay().save(path).expect("Could not save replay");
+    }
+}